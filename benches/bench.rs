@@ -20,7 +20,7 @@ use crossbeam_queue::{ArrayQueue, SegQueue};
 use crossbeam_channel::{bounded, unbounded};
 use crossbeam_utils::atomic::AtomicCell;
 
-use wrr::IWRRSelector;
+use pi_wrr::{IWRRSelector, WeightedQueues, SyncWeightedQueues};
 
 #[bench]
 fn bench_1_0(b: &mut Bencher) {
@@ -88,6 +88,45 @@ fn bench_30_1(b: &mut Bencher) {
     });
 }
 
+#[bench]
+fn bench_30_1_by_counts(b: &mut Bencher) {
+    const COUNT: usize = 10000000;
+    let mut selector = IWRRSelector::new([30, 1]);
+
+    thread::sleep(Duration::from_secs(1));
+
+    b.iter(|| {
+        let [x, y] = selector.select_counts(COUNT);
+        assert_eq!(COUNT, x + y);
+    });
+}
+
+#[bench]
+fn bench_30_1_by_into(b: &mut Bencher) {
+    const COUNT: usize = 10000000;
+    let mut selector = IWRRSelector::new([30, 1]);
+    let mut buf = vec![0; 1024];
+
+    thread::sleep(Duration::from_secs(1));
+
+    b.iter(|| {
+        let mut x = 0;
+        let mut y = 0;
+
+        for _ in 0..COUNT / buf.len() {
+            let len = selector.select_into(&mut buf);
+            for pos in &buf[..len] {
+                match pos {
+                    0 => x += 1,
+                    1 => y += 1,
+                    _ => (),
+                }
+            }
+        }
+        assert_eq!(COUNT / buf.len() * buf.len(), x + y);
+    });
+}
+
 #[bench]
 fn bench_rand(b: &mut Bencher) {
     const COUNT: usize = 10000000;
@@ -390,6 +429,23 @@ fn bench_deque_by_front(b: &mut Bencher) {
     println!("!!!!!!r: {:?}", r);
 }
 
+#[bench]
+fn bench_weighted_queues(b: &mut Bencher) {
+    const COUNT: usize = 10000000;
+    let mut queues = WeightedQueues::new([30, 1]);
+
+    thread::sleep(Duration::from_secs(1));
+
+    let mut r = None;
+    b.iter(|| {
+        for n in 0..COUNT {
+            queues.push(n & 1, n);
+            r = queues.pop();
+        }
+    });
+    println!("!!!!!!r: {:?}", r);
+}
+
 #[bench]
 fn bench_vec(b: &mut Bencher) {
     const COUNT: usize = 10000000;
@@ -554,6 +610,39 @@ fn bench_seq_queue(b: &mut Bencher) {
     println!("!!!!!!r: {:?}", r);
 }
 
+#[bench]
+fn bench_sync_weighted_queues(b: &mut Bencher) {
+    const COUNT: usize = 10000000;
+
+    thread::sleep(Duration::from_secs(1));
+
+    let mut r = None;
+    let queues = Arc::new(SyncWeightedQueues::new([30, 1]));
+    b.iter(|| {
+        let queues_copy = queues.clone();
+        let join0 = thread::spawn(move || {
+            for _ in 0..COUNT {
+                r = queues_copy.pop_wait(Duration::from_secs(1));
+            }
+        });
+
+        let joins: Vec<_> = (0..4).map(|index| {
+            let queues_copy = queues.clone();
+            thread::spawn(move || {
+                for n in index * 2500000..(index + 1) * 2500000 {
+                    queues_copy.push(n & 1, n);
+                }
+            })
+        }).collect();
+
+        join0.join();
+        for join in joins {
+            join.join();
+        }
+    });
+    println!("!!!!!!r: {:?}", r);
+}
+
 #[bench]
 fn test_new_atomic_usize(b: &mut Bencher) {
     let mut atomic = Arc::new(AtomicUsize::new(0));
@@ -0,0 +1,228 @@
+#[cfg(feature = "std")]
+use std::hash::{BuildHasher, Hasher};
+#[cfg(feature = "std")]
+use std::collections::hash_map::RandomState;
+
+///
+/// 加权随机选择器，使用Vose的别名方法，选择的时间复杂度为O(1)，
+/// 别名表不会增量更新，改变权重后在下一次选择时以O(N)的时间复杂度完整重建
+///
+#[derive(Debug, Clone)]
+pub struct AliasSelector<const LEN: usize> {
+    seed:           u64,            //随机数种子
+    state:          u64,            //内部随机数生成器的状态
+    dirty:          bool,           //别名表是否需要重建
+    total_weight:   u64,            //权重的总和
+    probs:          [u64; LEN],     //每一列选择自身的阈值，阈值的上限为权重的总和
+    aliases:        [usize; LEN],   //每一列的别名位置
+    weights:        [u8; LEN],      //待选择的权重数组
+}
+
+#[cfg(feature = "std")]
+impl<const LEN: usize> Default for AliasSelector<LEN> {
+    /// 默认构建指定长度且权重相同的加权随机选择器
+    fn default() -> Self {
+        Self::new([1; LEN])
+    }
+}
+
+impl<const LEN: usize> AliasSelector<LEN> {
+    /// 构建指定待选择的权重数组的加权随机选择器，随机数种子在每个进程中都不同
+    #[cfg(feature = "std")]
+    pub fn new(weights: [u8; LEN]) -> Self {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_usize(LEN);
+
+        Self::with_seed(weights, hasher.finish())
+    }
+
+    /// 构建指定待选择的权重数组和随机数种子的加权随机选择器
+    pub fn with_seed(weights: [u8; LEN], seed: u64) -> Self {
+        for weight in weights {
+            if weight == u8::MAX {
+                panic!("Create AliasSelector failed, weight: {}, reason: invalid weight",
+                       weight);
+            }
+        }
+
+        let mut selector = AliasSelector {
+            seed,
+            state: seed,
+            dirty: true,
+            total_weight: 0,
+            probs: [0; LEN],
+            aliases: [0; LEN],
+            weights,
+        };
+        selector.rebuild();
+
+        selector
+    }
+
+    /// 获取待选择的权重数组的长度
+    pub const fn len(&self) -> usize {
+        LEN
+    }
+
+    /// 判断待选择的权重数组是否为空
+    pub const fn is_empty(&self) -> bool {
+        LEN == 0
+    }
+
+    /// 获取随机数种子
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// 获取权重的总和
+    pub fn total_weight(&self) -> u64 {
+        self.weights.iter().map(|weight| *weight as u64).sum()
+    }
+
+    /// 尝试获取指定位置的权重
+    pub fn try_weight(&self, index: usize) -> Option<u8> {
+        if index >= self.len() {
+            None
+        } else {
+            Some(self.weights[index])
+        }
+    }
+
+    /// 改变指定位置的权重，改变成功则返回指定位置的上个权重，
+    /// 别名表只在权重真正改变后的下一次选择时完整重建，连续多次改变只会重建一次
+    pub fn change_weight(&mut self,
+                         index: usize,
+                         weight: u8) -> Option<u8> {
+        if weight == u8::MAX {
+            return None;
+        }
+
+        let old = self.try_weight(index)?;
+        if old != weight {
+            self.weights[index] = weight;
+            self.dirty = true;
+        }
+
+        Some(old)
+    }
+
+    /// 使用内部的随机数生成器，根据权重随机选择，并返回被选择的位置，所有权重都为零则panic
+    pub fn select(&mut self) -> usize {
+        if let Some(pos) = self.try_select() {
+            pos
+        } else {
+            panic!("Select failed, len: {}, reason: all weights are zero",
+                   self.len());
+        }
+    }
+
+    /// 使用内部的随机数生成器，尝试根据权重随机选择，并返回被选择的位置，所有权重都为零则返回空
+    pub fn try_select(&mut self) -> Option<usize> {
+        let mut state = self.state;
+        let pos = self.try_select_with(|| wyrand(&mut state));
+        self.state = state;
+
+        pos
+    }
+
+    /// 使用指定的随机数生成器，根据权重随机选择，并返回被选择的位置，所有权重都为零则panic
+    pub fn select_with<F>(&mut self, rng: F) -> usize
+        where F: FnMut() -> u64 {
+        if let Some(pos) = self.try_select_with(rng) {
+            pos
+        } else {
+            panic!("Select failed, len: {}, reason: all weights are zero",
+                   self.len());
+        }
+    }
+
+    /// 使用指定的随机数生成器，尝试根据权重随机选择，并返回被选择的位置，所有权重都为零则返回空
+    pub fn try_select_with<F>(&mut self, mut rng: F) -> Option<usize>
+        where F: FnMut() -> u64 {
+        if self.dirty {
+            self.rebuild();
+        }
+
+        if self.total_weight == 0 {
+            //没有可以被选择的位置
+            return None;
+        }
+
+        //随机选择一列，再根据阈值选择这一列自身或别名位置
+        let column = range(rng(), LEN as u64) as usize;
+        if range(rng(), self.total_weight) < self.probs[column] {
+            Some(column)
+        } else {
+            Some(self.aliases[column])
+        }
+    }
+
+    /// 重置选择器，内部的随机数生成器会从随机数种子重新开始
+    pub fn reset(&mut self) {
+        self.state = self.seed;
+    }
+
+    // 重建别名表，所有阈值都使用整数运算，所以不会有精度误差
+    fn rebuild(&mut self) {
+        let total_weight = self.total_weight();
+        let mut scaled = [0u64; LEN];
+        let mut smalls = [0usize; LEN];
+        let mut larges = [0usize; LEN];
+        let mut small_len = 0;
+        let mut large_len = 0;
+
+        for (index, weight) in self.weights.iter().enumerate() {
+            //每一列的容量为权重的总和，所以按列数放大权重
+            scaled[index] = *weight as u64 * LEN as u64;
+            self.aliases[index] = index;
+            if scaled[index] < total_weight {
+                smalls[small_len] = index;
+                small_len += 1;
+            } else {
+                larges[large_len] = index;
+                large_len += 1;
+            }
+        }
+
+        while small_len > 0 && large_len > 0 {
+            //用容量有剩余的位置填满容量不足的列
+            small_len -= 1;
+            let small = smalls[small_len];
+            let large = larges[large_len - 1];
+
+            self.probs[small] = scaled[small];
+            self.aliases[small] = large;
+            scaled[large] -= total_weight - scaled[small];
+            if scaled[large] < total_weight {
+                large_len -= 1;
+                smalls[small_len] = large;
+                small_len += 1;
+            }
+        }
+
+        //剩余的列只会选择自身
+        for large in &larges[..large_len] {
+            self.probs[*large] = total_weight;
+        }
+        for small in &smalls[..small_len] {
+            self.probs[*small] = total_weight;
+        }
+
+        self.total_weight = total_weight;
+        self.dirty = false;
+    }
+}
+
+// 生成下一个随机数
+#[inline]
+fn wyrand(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0xa076_1d64_78bd_642f);
+    let t = (*state as u128) * ((*state ^ 0xe703_7ed1_a0b4_28db) as u128);
+    (t >> 64) as u64 ^ t as u64
+}
+
+// 将随机数映射到[0, n)
+#[inline]
+fn range(random: u64, n: u64) -> u64 {
+    ((random as u128 * n as u128) >> 64) as u64
+}
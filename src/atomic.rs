@@ -0,0 +1,243 @@
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{Weight, WrrError};
+
+/// 打包状态中轮数的偏移
+const ROUND_SHIFT: u32 = 56;
+
+/// 打包状态中位置的掩码
+const POS_MASK: u64 = (1 << ROUND_SHIFT) - 1;
+
+///
+/// 可以在多个线程间共享的交替加权轮询选择器，
+/// 当前选择的位置和轮数被打包在一个原子变量中，所有线程的选择共同组成与IWRRSelector相同的选择序列，
+/// 只在支持64位原子操作的目标平台上可用
+///
+#[derive(Debug)]
+pub struct AtomicIWRRSelector<const LEN: usize> {
+    state:      AtomicU64,  //打包的当前选择的位置和轮数
+    max_weight: u8,         //最大的权重
+    eligible:   usize,      //权重不为零的位置数量
+    weights:    [u8; LEN],  //待选择的权重数组
+}
+
+impl<const LEN: usize> Default for AtomicIWRRSelector<LEN> {
+    /// 默认构建指定长度且权重相同的交替加权轮询选择器，长度为零时构建空的选择器，尝试选择总是返回空
+    fn default() -> Self {
+        if LEN == 0 {
+            return AtomicIWRRSelector {
+                state: AtomicU64::new(0),
+                max_weight: 0,
+                eligible: 0,
+                weights: [0; LEN],
+            };
+        }
+
+        Self::new([1; LEN])
+    }
+}
+
+impl<const LEN: usize> Clone for AtomicIWRRSelector<LEN> {
+    fn clone(&self) -> Self {
+        AtomicIWRRSelector {
+            state: AtomicU64::new(self.state.load(Ordering::Acquire)),
+            max_weight: self.max_weight,
+            eligible: self.eligible,
+            weights: self.weights,
+        }
+    }
+}
+
+impl<const LEN: usize> AtomicIWRRSelector<LEN> {
+    /// 构建指定待选择的权重数组的交替加权轮询选择器，权重数组无效则立即panic
+    pub fn new(weights: [u8; LEN]) -> Self {
+        match Self::try_new(weights) {
+            Err(e) => panic!("Create AtomicIWRRSelector failed, reason: {}", e),
+            Ok(selector) => selector,
+        }
+    }
+
+    /// 尝试构建指定待选择的权重数组的交替加权轮询选择器，
+    /// 权重数组为空、包含无效的权重或所有权重都为零则返回错误
+    pub fn try_new(weights: [u8; LEN]) -> Result<Self, WrrError> {
+        if LEN == 0 {
+            return Err(WrrError::EmptySelector);
+        }
+
+        let mut max_weight = 0;
+        let mut eligible = 0;
+        for (index, weight) in weights.iter().enumerate() {
+            if !weight.is_valid() {
+                return Err(WrrError::InvalidWeight {
+                    index,
+                    weight: *weight as u64,
+                });
+            }
+
+            if *weight > 0 {
+                eligible += 1;
+            }
+
+            if max_weight < *weight {
+                //替换最大的权重
+                max_weight = *weight;
+            }
+        }
+
+        if max_weight == 0 {
+            return Err(WrrError::AllWeightsZero);
+        }
+
+        Ok(AtomicIWRRSelector {
+            state: AtomicU64::new(0),
+            max_weight,
+            eligible,
+            weights,
+        })
+    }
+
+    /// 获取待选择的权重数组的长度
+    pub const fn len(&self) -> usize {
+        LEN
+    }
+
+    /// 判断待选择的权重数组是否为空
+    pub const fn is_empty(&self) -> bool {
+        LEN == 0
+    }
+
+    /// 获取选择的当前轮数
+    pub fn round(&self) -> u8 {
+        unpack(self.state.load(Ordering::Acquire)).1
+    }
+
+    /// 获取当前选择的位置
+    pub fn pos(&self) -> usize {
+        unpack(self.state.load(Ordering::Acquire)).0
+    }
+
+    /// 获取最大的权重
+    pub fn max_weight(&self) -> u8 {
+        self.max_weight
+    }
+
+    /// 获取权重不为零的位置数量，为零时没有可以被选择的位置
+    pub fn eligible_len(&self) -> usize {
+        self.eligible
+    }
+
+    /// 尝试获取指定位置的权重
+    pub fn try_weight(&self, index: usize) -> Option<u8> {
+        if index >= self.len() {
+            None
+        } else {
+            Some(self.weights[index])
+        }
+    }
+
+    /// 改变指定位置的权重，改变成功则返回指定位置的上个权重，
+    /// 改变权重需要独占选择器，所以不会与选择并发
+    pub fn change_weight(&mut self,
+                         index: usize,
+                         weight: u8) -> Option<u8> {
+        if !weight.is_valid() {
+            return None;
+        }
+
+        let old = self.try_weight(index)?;
+        self.weights[index] = weight;
+
+        if old == 0 && weight > 0 {
+            self.eligible += 1;
+        } else if old > 0 && weight == 0 {
+            self.eligible -= 1;
+        }
+
+        if weight > self.max_weight {
+            //替换最大的权重
+            self.max_weight = weight;
+        } else if old == self.max_weight && weight < old {
+            //减少了最大的权重，则重新计算最大的权重
+            self.max_weight = self.weights.iter().copied().max().unwrap_or(0);
+        }
+
+        Some(old)
+    }
+
+    /// 根据权重选择，并返回被选择的位置，所有权重都为零则panic
+    pub fn select(&self) -> usize {
+        if let Some(pos) = self.try_select() {
+            pos
+        } else {
+            panic!("Select failed, len: {}, reason: all weights are zero",
+                   self.len());
+        }
+    }
+
+    /// 尝试根据权重选择，并返回被选择的位置，所有权重都为零则返回空
+    pub fn try_select(&self) -> Option<usize> {
+        if self.eligible == 0 {
+            //没有可以被选择的位置
+            return None;
+        }
+
+        let mut current = self.state.load(Ordering::Acquire);
+        loop {
+            //根据当前状态计算被选择的位置和下一个状态，并尝试替换当前状态
+            let (pos, round) = unpack(current);
+            let (selected, pos, round) = self.next(pos, round);
+            match self.state.compare_exchange_weak(current,
+                                                   pack(pos, round),
+                                                   Ordering::AcqRel,
+                                                   Ordering::Acquire) {
+                Err(actual) => {
+                    //其它线程已完成选择，则从最新的状态重新计算
+                    current = actual;
+                },
+                Ok(_) => {
+                    return Some(selected);
+                },
+            }
+        }
+    }
+
+    /// 重置选择器
+    pub fn reset(&self) {
+        self.state.store(0, Ordering::Release);
+    }
+
+    // 从指定的位置和轮数开始选择，返回被选择的位置，以及选择后的位置和轮数
+    fn next(&self, mut pos: usize, mut round: u8) -> (usize, usize, u8) {
+        loop {
+            while pos < self.len() {
+                let weight = self.weights[pos];
+                pos += 1;
+                if weight > round {
+                    //返回被选择的位置
+                    return (pos - 1, pos, round);
+                }
+            }
+
+            if round + 1 >= self.max_weight {
+                //完成当前周期的选择，则重置位置和轮数
+                round = 0;
+            } else {
+                //完成当前轮的选择，则重置位置，并继续下一轮的选择
+                round += 1;
+            }
+            pos = 0;
+        }
+    }
+}
+
+// 打包位置和轮数
+#[inline]
+fn pack(pos: usize, round: u8) -> u64 {
+    ((round as u64) << ROUND_SHIFT) | pos as u64
+}
+
+// 解包位置和轮数
+#[inline]
+fn unpack(state: u64) -> (usize, u8) {
+    ((state & POS_MASK) as usize, (state >> ROUND_SHIFT) as u8)
+}
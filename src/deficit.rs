@@ -0,0 +1,189 @@
+///
+/// 赤字轮询选择器，按选择的开销而不是选择的次数来公平分配
+///
+#[derive(Debug, Clone)]
+pub struct DeficitRRSelector<const LEN: usize> {
+    pos:        usize,          //当前选择的位置
+    visited:    bool,           //当前选择的位置是否已获得本轮的配额
+    quantum:    usize,          //单位权重的配额
+    eligible:   usize,          //权重不为零的位置数量
+    deficits:   [usize; LEN],   //待选择的赤字数组
+    weights:    [u8; LEN],      //待选择的权重数组
+}
+
+impl<const LEN: usize> Default for DeficitRRSelector<LEN> {
+    /// 默认构建指定长度且权重相同的赤字轮询选择器
+    fn default() -> Self {
+        Self::new([1; LEN])
+    }
+}
+
+impl<const LEN: usize> DeficitRRSelector<LEN> {
+    /// 构建指定待选择的权重数组的赤字轮询选择器，单位权重的配额为1
+    pub fn new(weights: [u8; LEN]) -> Self {
+        Self::with_quantum(weights, 1)
+    }
+
+    /// 构建指定待选择的权重数组和单位权重配额的赤字轮询选择器
+    pub fn with_quantum(weights: [u8; LEN], quantum: usize) -> Self {
+        let mut eligible = 0;
+        for weight in weights {
+            if weight == u8::MAX {
+                panic!("Create DeficitRRSelector failed, weight: {}, reason: invalid weight",
+                       weight);
+            }
+
+            if weight > 0 {
+                eligible += 1;
+            }
+        }
+
+        if quantum == 0 {
+            panic!("Create DeficitRRSelector failed, quantum: {}, reason: invalid quantum",
+                   quantum);
+        }
+
+        DeficitRRSelector {
+            pos: 0,
+            visited: false,
+            quantum,
+            eligible,
+            deficits: [0; LEN],
+            weights,
+        }
+    }
+
+    /// 获取待选择的权重数组的长度
+    pub const fn len(&self) -> usize {
+        LEN
+    }
+
+    /// 判断待选择的权重数组是否为空
+    pub const fn is_empty(&self) -> bool {
+        LEN == 0
+    }
+
+    /// 获取单位权重的配额
+    pub fn quantum(&self) -> usize {
+        self.quantum
+    }
+
+    /// 尝试获取指定位置的权重
+    pub fn try_weight(&self, index: usize) -> Option<u8> {
+        if index >= self.len() {
+            None
+        } else {
+            Some(self.weights[index])
+        }
+    }
+
+    /// 尝试获取指定位置的赤字
+    pub fn try_deficit(&self, index: usize) -> Option<usize> {
+        if index >= self.len() {
+            None
+        } else {
+            Some(self.deficits[index])
+        }
+    }
+
+    /// 改变指定位置的权重，改变成功则返回指定位置的上个权重，新的权重从指定位置的下一轮配额开始生效
+    pub fn change_weight(&mut self,
+                         index: usize,
+                         weight: u8) -> Option<u8> {
+        if weight == u8::MAX {
+            return None;
+        }
+
+        if let Some(old) = self.try_weight(index) {
+            self.weights[index] = weight;
+            if old == 0 && weight > 0 {
+                self.eligible += 1;
+            } else if old > 0 && weight == 0 {
+                //被禁用的位置不再保留赤字
+                self.eligible -= 1;
+                self.deficits[index] = 0;
+            }
+            Some(old)
+        } else {
+            None
+        }
+    }
+
+    /// 获取当前选择的位置
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// 根据权重选择，并返回被选择的位置，每次选择的开销为1，所有权重都为零则panic
+    pub fn select(&mut self) -> usize {
+        self.select_with_cost(|_| 1)
+    }
+
+    /// 尝试根据权重选择，并返回被选择的位置，每次选择的开销为1，所有权重都为零则返回空
+    pub fn try_select(&mut self) -> Option<usize> {
+        self.try_select_with_cost(|_| 1)
+    }
+
+    /// 根据权重和指定位置本次选择的开销选择，并返回被选择的位置，
+    /// 被选择位置的赤字会扣除本次选择的开销，所有权重都为零则panic
+    pub fn select_with_cost<F>(&mut self, cost: F) -> usize
+        where F: FnMut(usize) -> usize {
+        if let Some(pos) = self.try_select_with_cost(cost) {
+            pos
+        } else {
+            panic!("Select failed, len: {}, reason: all weights are zero",
+                   self.len());
+        }
+    }
+
+    /// 尝试根据权重和指定位置本次选择的开销选择，并返回被选择的位置，
+    /// 被选择位置的赤字会扣除本次选择的开销，所有权重都为零则返回空
+    pub fn try_select_with_cost<F>(&mut self, mut cost: F) -> Option<usize>
+        where F: FnMut(usize) -> usize {
+        if self.eligible == 0 {
+            //没有可以被选择的位置
+            return None;
+        }
+
+        loop {
+            if self.pos >= self.len() {
+                //完成当前轮的选择，则重置位置，并继续下一轮的选择
+                self.pos = 0;
+                self.visited = false;
+            }
+
+            let pos = self.pos;
+            let weight = self.weights[pos];
+            if weight == 0 {
+                //被忽略，则继续下一个位置的选择
+                self.pos += 1;
+                self.visited = false;
+                continue;
+            }
+
+            if !self.visited {
+                //首次访问当前位置，则增加当前位置本轮的配额
+                self.deficits[pos] += weight as usize * self.quantum;
+                self.visited = true;
+            }
+
+            let cost = cost(pos);
+            if self.deficits[pos] >= cost {
+                //赤字足够，则扣除开销，并返回被选择的位置
+                self.deficits[pos] -= cost;
+                return Some(pos);
+            }
+
+            //赤字不足，则保留赤字，并继续下一个位置的选择
+            self.pos += 1;
+            self.visited = false;
+        }
+    }
+
+    /// 重置选择器
+    pub fn reset(&mut self) {
+        self.pos = 0;
+        self.visited = false;
+        self.deficits = [0; LEN];
+    }
+}
@@ -0,0 +1,279 @@
+use alloc::vec::Vec;
+
+use crate::{Weight, WrrError};
+
+///
+/// 动态选择器中位置的唯一标识，位置被移除后标识不会被复用
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SlotId {
+    index:      usize,  //位置的下标
+    generation: usize,  //位置的代数
+}
+
+impl SlotId {
+    /// 获取位置的下标，下标在位置被移除后可能被新的位置复用
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+///
+/// 动态选择器中的位置
+///
+#[derive(Debug, Clone)]
+struct Slot<W: Weight> {
+    generation: usize,      //位置的代数
+    weight:     Option<W>,  //位置的权重，为空表示位置已被移除
+}
+
+///
+/// 交替加权轮询选择器，权重类型为u8，可以在运行时增加和移除位置
+///
+pub type DynIWRRSelector = GenericDynIWRRSelector<u8>;
+
+///
+/// 指定权重类型的交替加权轮询选择器，可以在运行时增加和移除位置
+///
+#[derive(Debug, Clone)]
+pub struct GenericDynIWRRSelector<W: Weight> {
+    pos:        usize,          //当前选择的位置
+    round:      W,              //选择的当前轮数
+    max_weight: W,              //最大的权重
+    eligible:   usize,          //权重不为零的位置数量
+    len:        usize,          //位置的数量
+    slots:      Vec<Slot<W>>,   //待选择的位置数组
+    frees:      Vec<usize>,     //已被移除的位置的下标
+}
+
+impl<W: Weight> Default for GenericDynIWRRSelector<W> {
+    /// 默认构建没有位置的交替加权轮询选择器
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W: Weight> GenericDynIWRRSelector<W> {
+    /// 构建没有位置的交替加权轮询选择器
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// 构建指定位置容量的交替加权轮询选择器
+    pub fn with_capacity(capacity: usize) -> Self {
+        GenericDynIWRRSelector {
+            pos: 0,
+            round: W::ZERO,
+            max_weight: W::ZERO,
+            eligible: 0,
+            len: 0,
+            slots: Vec::with_capacity(capacity),
+            frees: Vec::new(),
+        }
+    }
+
+    /// 获取位置的数量
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// 判断是否没有位置
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 获取选择的当前轮数
+    pub fn round(&self) -> W {
+        self.round
+    }
+
+    /// 获取最大的权重
+    pub fn max_weight(&self) -> W {
+        self.max_weight
+    }
+
+    /// 获取权重不为零的位置数量，为零时没有可以被选择的位置
+    pub fn eligible_len(&self) -> usize {
+        self.eligible
+    }
+
+    /// 判断指定位置是否存在
+    pub fn contains(&self, id: SlotId) -> bool {
+        self.try_weight(id).is_some()
+    }
+
+    /// 增加指定权重的位置，并返回位置的唯一标识，权重无效则立即panic
+    pub fn push(&mut self, weight: W) -> SlotId {
+        match self.try_push(weight) {
+            Err(e) => panic!("Push slot failed, reason: {}", e),
+            Ok(id) => id,
+        }
+    }
+
+    /// 尝试增加指定权重的位置，并返回位置的唯一标识，权重无效则返回错误
+    pub fn try_push(&mut self, weight: W) -> Result<SlotId, WrrError> {
+        let index = self.frees.last().copied().unwrap_or(self.slots.len());
+        if !weight.is_valid() {
+            return Err(WrrError::InvalidWeight {
+                index,
+                weight: weight.as_u64(),
+            });
+        }
+
+        let generation = if let Some(index) = self.frees.pop() {
+            //复用已被移除的位置
+            let slot = &mut self.slots[index];
+            slot.weight = Some(weight);
+            slot.generation
+        } else {
+            self.slots.push(Slot {
+                generation: 0,
+                weight: Some(weight),
+            });
+            0
+        };
+
+        self.len += 1;
+        if weight > W::ZERO {
+            self.eligible += 1;
+        }
+        if weight > self.max_weight {
+            //替换最大的权重
+            self.max_weight = weight;
+        }
+
+        Ok(SlotId {
+            index,
+            generation,
+        })
+    }
+
+    /// 移除指定位置，移除成功则返回指定位置的权重，其它位置在当前周期的选择不受影响
+    pub fn remove(&mut self, id: SlotId) -> Option<W> {
+        let old = self.try_weight(id)?;
+
+        let slot = &mut self.slots[id.index];
+        slot.weight = None;
+        slot.generation += 1;
+        self.frees.push(id.index);
+        self.len -= 1;
+
+        self.update_weight(old, W::ZERO);
+        Some(old)
+    }
+
+    /// 尝试获取指定位置的权重
+    pub fn try_weight(&self, id: SlotId) -> Option<W> {
+        match self.slots.get(id.index) {
+            Some(slot) if slot.generation == id.generation => slot.weight,
+            _ => None,
+        }
+    }
+
+    /// 改变指定位置的权重，改变成功则返回指定位置的上个权重
+    pub fn change_weight(&mut self,
+                         id: SlotId,
+                         weight: W) -> Option<W> {
+        if !weight.is_valid() {
+            return None;
+        }
+
+        let old = self.try_weight(id)?;
+        self.slots[id.index].weight = Some(weight);
+        self.update_weight(old, weight);
+
+        Some(old)
+    }
+
+    /// 获取所有位置和位置的权重的迭代器
+    pub fn iter(&self) -> impl Iterator<Item = (SlotId, W)> + '_ {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            slot.weight.map(|weight| {
+                (SlotId {
+                    index,
+                    generation: slot.generation,
+                }, weight)
+            })
+        })
+    }
+
+    /// 获取当前选择的位置
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// 根据权重选择，并返回被选择的位置，没有权重不为零的位置则panic
+    pub fn select(&mut self) -> SlotId {
+        if let Some(id) = self.try_select() {
+            id
+        } else {
+            panic!("Select failed, len: {}, reason: all weights are zero",
+                   self.len());
+        }
+    }
+
+    /// 尝试根据权重选择，并返回被选择的位置，没有权重不为零的位置则返回空
+    pub fn try_select(&mut self) -> Option<SlotId> {
+        if self.eligible == 0 {
+            //没有可以被选择的位置
+            return None;
+        }
+
+        loop {
+            for index in self.pos..self.slots.len() {
+                let slot = &self.slots[index];
+                self.pos += 1;
+                match slot.weight {
+                    Some(weight) if weight > self.round => {
+                        //返回被选择的位置
+                        return Some(SlotId {
+                            index,
+                            generation: slot.generation,
+                        });
+                    },
+                    _ => {
+                        //被忽略或已被移除，则继续下一个位置的选择
+                        continue;
+                    },
+                }
+            }
+
+            if self.round + W::ONE >= self.max_weight {
+                //完成当前周期的选择，则重置选择器
+                self.reset();
+            } else {
+                //完成当前轮的选择，则重置位置，并继续下一轮的选择
+                self.pos = 0;
+                self.round = self.round + W::ONE;
+            }
+        }
+    }
+
+    /// 重置选择器
+    pub fn reset(&mut self) {
+        self.round = W::ZERO;
+        self.pos = 0;
+    }
+
+    // 位置的权重改变后，保持最大的权重和权重不为零的位置数量准确
+    fn update_weight(&mut self, old: W, weight: W) {
+        if old == W::ZERO && weight > W::ZERO {
+            self.eligible += 1;
+        } else if old > W::ZERO && weight == W::ZERO {
+            self.eligible -= 1;
+        }
+
+        if weight > self.max_weight {
+            //替换最大的权重
+            self.max_weight = weight;
+        } else if old == self.max_weight && weight < old {
+            //减少了最大的权重，则重新计算最大的权重
+            self.max_weight = self
+                .slots
+                .iter()
+                .filter_map(|slot| slot.weight)
+                .max()
+                .unwrap_or(W::ZERO);
+        }
+    }
+}
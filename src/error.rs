@@ -0,0 +1,34 @@
+use core::fmt;
+
+///
+/// 选择器的错误
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WrrError {
+    /// 指定位置的权重无效，权重必须小于权重类型的上限
+    InvalidWeight { index: usize, weight: u64 },
+    /// 指定位置超出了待选择的权重数组的长度
+    IndexOutOfRange { index: usize, len: usize },
+    /// 所有位置的权重都为零
+    AllWeightsZero,
+    /// 待选择的权重数组为空
+    EmptySelector,
+}
+
+impl fmt::Display for WrrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WrrError::InvalidWeight { index, weight } => {
+                write!(f, "invalid weight, index: {}, weight: {}", index, weight)
+            },
+            WrrError::IndexOutOfRange { index, len } => {
+                write!(f, "index out of range, index: {}, len: {}", index, len)
+            },
+            WrrError::AllWeightsZero => write!(f, "all weights are zero"),
+            WrrError::EmptySelector => write!(f, "empty selector"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for WrrError {}
@@ -1,44 +1,350 @@
+#![no_std]
 
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+use core::iter::Take;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+mod weight;
+mod error;
+mod smooth;
+mod deficit;
+mod stride;
+mod alias;
+#[cfg(feature = "alloc")]
+mod dynamic;
+#[cfg(feature = "alloc")]
+mod pool;
+#[cfg(feature = "alloc")]
+mod queues;
+#[cfg(feature = "std")]
+mod sync_queues;
+#[cfg(feature = "std")]
+mod receiver;
+#[cfg(feature = "std")]
+mod select;
+#[cfg(target_has_atomic = "64")]
+mod atomic;
+#[cfg(feature = "serde")]
+mod serialize;
+
+pub use weight::Weight;
+pub use error::WrrError;
+pub use smooth::SmoothWRRSelector;
+pub use deficit::DeficitRRSelector;
+pub use stride::StrideSelector;
+pub use alias::AliasSelector;
+#[cfg(feature = "alloc")]
+pub use dynamic::{SlotId, DynIWRRSelector, GenericDynIWRRSelector};
+#[cfg(feature = "alloc")]
+pub use pool::{WeightedPool, GenericWeightedPool};
+#[cfg(feature = "alloc")]
+pub use queues::{WeightedQueues, GenericWeightedQueues};
+#[cfg(feature = "std")]
+pub use sync_queues::{SyncWeightedQueues, GenericSyncWeightedQueues};
+#[cfg(feature = "std")]
+pub use receiver::{WeightedReceiver, GenericWeightedReceiver};
+#[cfg(feature = "std")]
+pub use select::{LocalBoxFuture, WeightedSelect, GenericWeightedSelect};
+#[cfg(target_has_atomic = "64")]
+pub use atomic::AtomicIWRRSelector;
+
+///
+/// 加权选择器的通用接口，用于编写与选择算法无关的负载均衡和队列调度
+///
+pub trait Selector {
+    /// 权重的类型
+    type Weight: Copy;
+
+    /// 获取待选择的权重数组的长度
+    fn len(&self) -> usize;
+
+    /// 判断待选择的权重数组是否为空
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 尝试获取指定位置的权重
+    fn try_weight(&self, index: usize) -> Option<Self::Weight>;
+
+    /// 改变指定位置的权重，改变成功则返回指定位置的上个权重
+    fn change_weight(&mut self,
+                     index: usize,
+                     weight: Self::Weight) -> Option<Self::Weight>;
+
+    /// 根据权重选择，并返回被选择的位置，所有权重都为零则panic
+    fn select(&mut self) -> usize;
+
+    /// 尝试根据权重选择，并返回被选择的位置，没有可以被选择的位置则返回空
+    fn try_select(&mut self) -> Option<usize>;
+
+    /// 重置选择器
+    fn reset(&mut self);
+}
+
+// 使用选择器的同名方法实现选择器的通用接口
+macro_rules! impl_selector {
+    ($selector:ident, $weight:ty) => {
+        impl<const LEN: usize> Selector for $selector<LEN> {
+            type Weight = $weight;
+
+            #[inline]
+            fn len(&self) -> usize {
+                $selector::len(self)
+            }
+
+            #[inline]
+            fn try_weight(&self, index: usize) -> Option<Self::Weight> {
+                $selector::try_weight(self, index)
+            }
+
+            #[inline]
+            fn change_weight(&mut self,
+                             index: usize,
+                             weight: Self::Weight) -> Option<Self::Weight> {
+                $selector::change_weight(self, index, weight)
+            }
+
+            #[inline]
+            fn select(&mut self) -> usize {
+                $selector::select(self)
+            }
+
+            #[inline]
+            fn try_select(&mut self) -> Option<usize> {
+                $selector::try_select(self)
+            }
+
+            #[inline]
+            fn reset(&mut self) {
+                $selector::reset(self)
+            }
+        }
+    };
+}
+
+impl<W: Weight, const LEN: usize> Selector for GenericIWRRSelector<W, LEN> {
+    type Weight = W;
+
+    #[inline]
+    fn len(&self) -> usize {
+        GenericIWRRSelector::len(self)
+    }
+
+    #[inline]
+    fn try_weight(&self, index: usize) -> Option<Self::Weight> {
+        GenericIWRRSelector::try_weight(self, index)
+    }
+
+    #[inline]
+    fn change_weight(&mut self,
+                     index: usize,
+                     weight: Self::Weight) -> Option<Self::Weight> {
+        GenericIWRRSelector::change_weight(self, index, weight)
+    }
+
+    #[inline]
+    fn select(&mut self) -> usize {
+        GenericIWRRSelector::select(self)
+    }
+
+    #[inline]
+    fn try_select(&mut self) -> Option<usize> {
+        GenericIWRRSelector::try_select(self)
+    }
+
+    #[inline]
+    fn reset(&mut self) {
+        GenericIWRRSelector::reset(self)
+    }
+}
+
+impl_selector!(SmoothWRRSelector, u8);
+impl_selector!(DeficitRRSelector, u8);
+impl_selector!(StrideSelector, u8);
+impl_selector!(AliasSelector, u8);
+#[cfg(target_has_atomic = "64")]
+impl_selector!(AtomicIWRRSelector, u8);
+
+///
+/// 交替加权轮询选择器，权重类型为u8
+///
+pub type IWRRSelector<const LEN: usize> = GenericIWRRSelector<u8, LEN>;
 
 ///
-/// 交替加权轮询选择器
+/// 交替加权轮询选择器，权重类型为usize
+///
+pub type IWRRSelectorByWider<const LEN: usize> = GenericIWRRSelector<usize, LEN>;
+
+///
+/// 改变权重的生效策略
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChangePolicy {
+    /// 立即生效，当前轮的剩余位置使用新的权重
+    #[default]
+    Immediate,
+    /// 在当前轮结束后生效
+    NextRound,
+    /// 在当前周期结束后生效
+    NextCycle,
+}
+
+///
+/// 指定权重类型的交替加权轮询选择器
 ///
 #[derive(Debug, Clone)]
-pub struct IWRRSelector<const LEN: usize> {
-    pos:        usize,      //当前选择的位置
-    round:      u8,         //选择的当前轮数
-    max_weight: u8,         //最大的权重
-    weights:    [u8; LEN],  //待选择的权重数组
+pub struct GenericIWRRSelector<W: Weight, const LEN: usize> {
+    pos:         usize,          //当前选择的位置
+    round:       W,              //选择的当前轮数
+    max_weight:  W,              //最大的权重
+    eligible:    usize,          //权重不为零的位置数量
+    unavailable: usize,          //不可用的位置数量
+    policy:      ChangePolicy,   //改变权重的生效策略
+    changed:     bool,           //是否有未生效的权重
+    normalized:  bool,           //是否按所有权重的最大公约数约简生效的权重
+    skipping:    bool,           //当前周期中是否有被提前连续选择的位置
+    weights:     [W; LEN],       //待选择的权重数组
+    pending:     [W; LEN],       //最新改变的权重数组，按生效策略替换待选择的权重数组
+    skips:       [W; LEN],       //每个位置在当前周期中被提前连续选择，需要跳过的次数
+    available:   [bool; LEN],    //每个位置是否可用，不可用的位置不会被选择
 }
 
-impl<const LEN: usize> Default for IWRRSelector<LEN> {
-    /// 默认构建指定长度且权重相同的交替加权轮询选择器
+impl<W: Weight, const LEN: usize> Default for GenericIWRRSelector<W, LEN> {
+    /// 默认构建指定长度且权重相同的交替加权轮询选择器，长度为零时构建空的选择器，尝试选择总是返回空
     fn default() -> Self {
-        Self::new([1; LEN])
+        if LEN == 0 {
+            return Self::with_weights([W::ZERO; LEN], W::ZERO, 0);
+        }
+
+        Self::new([W::ONE; LEN])
     }
 }
 
-impl<const LEN: usize> IWRRSelector<LEN> {
-    /// 构建指定待选择的权重数组的交替加权轮询选择器
-    pub fn new(weights: [u8; LEN]) -> Self {
-        let mut max_weight = 0;
-        for weight in weights {
-            if weight >= u8::MAX {
-                panic!("Create IWRRSelector failed, weight: {}, reason: invalid weight",
-                       weight);
+// 为指定的权重类型实现交替加权轮询选择器的常量构建
+macro_rules! impl_const_new {
+    ($($weight:ty),*) => {
+        $(
+            impl<const LEN: usize> GenericIWRRSelector<$weight, LEN> {
+                /// 在常量上下文中构建指定待选择的权重数组的交替加权轮询选择器，
+                /// 权重数组无效会导致编译错误，在运行时构建则立即panic
+                pub const fn new_const(weights: [$weight; LEN]) -> Self {
+                    if LEN == 0 {
+                        panic!("Create IWRRSelector failed, reason: empty selector");
+                    }
+
+                    let mut max_weight = 0;
+                    let mut eligible = 0;
+                    let mut index = 0;
+                    while index < LEN {
+                        let weight = weights[index];
+                        if weight == <$weight>::MAX {
+                            panic!("Create IWRRSelector failed, reason: invalid weight");
+                        }
+
+                        if weight > 0 {
+                            eligible += 1;
+                        }
+
+                        if max_weight < weight {
+                            //替换最大的权重
+                            max_weight = weight;
+                        }
+                        index += 1;
+                    }
+
+                    if max_weight == 0 {
+                        panic!("Create IWRRSelector failed, reason: all weights are zero");
+                    }
+
+                    GenericIWRRSelector {
+                        round: 0,
+                        max_weight,
+                        pos: 0,
+                        eligible,
+                        policy: ChangePolicy::Immediate,
+                        changed: false,
+                        normalized: false,
+                        weights,
+                        skipping: false,
+                        pending: weights,
+                        skips: [0; LEN],
+                        unavailable: 0,
+                        available: [true; LEN],
+                    }
+                }
             }
+        )*
+    };
+}
 
-            if max_weight < weight {
+impl_const_new!(u8, u16, u32, u64, usize);
+
+impl<W: Weight, const LEN: usize> GenericIWRRSelector<W, LEN> {
+    /// 构建指定待选择的权重数组的交替加权轮询选择器，权重数组无效则立即panic
+    pub fn new(weights: [W; LEN]) -> Self {
+        match Self::try_new(weights) {
+            Err(e) => panic!("Create IWRRSelector failed, reason: {}", e),
+            Ok(selector) => selector,
+        }
+    }
+
+    /// 尝试构建指定待选择的权重数组的交替加权轮询选择器，
+    /// 权重数组为空、包含无效的权重或所有权重都为零则返回错误
+    pub fn try_new(weights: [W; LEN]) -> Result<Self, WrrError> {
+        if LEN == 0 {
+            return Err(WrrError::EmptySelector);
+        }
+
+        let mut max_weight = W::ZERO;
+        let mut eligible = 0;
+        for (index, weight) in weights.iter().enumerate() {
+            if !weight.is_valid() {
+                return Err(WrrError::InvalidWeight {
+                    index,
+                    weight: weight.as_u64(),
+                });
+            }
+
+            if *weight > W::ZERO {
+                eligible += 1;
+            }
+
+            if max_weight < *weight {
                 //替换最大的权重
-                max_weight = weight;
+                max_weight = *weight;
             }
         }
 
-        IWRRSelector {
-            round: 0,
+        if max_weight == W::ZERO {
+            return Err(WrrError::AllWeightsZero);
+        }
+
+        Ok(Self::with_weights(weights, max_weight, eligible))
+    }
+
+    // 构建指定已校验的权重数组、最大的权重和权重不为零的位置数量的交替加权轮询选择器
+    fn with_weights(weights: [W; LEN], max_weight: W, eligible: usize) -> Self {
+        GenericIWRRSelector {
+            round: W::ZERO,
             max_weight,
             pos: 0,
+            eligible,
+            policy: ChangePolicy::Immediate,
+            changed: false,
+            normalized: false,
             weights,
+            skipping: false,
+            pending: weights,
+            skips: [W::ZERO; LEN],
+            unavailable: 0,
+            available: [true; LEN],
         }
     }
 
@@ -47,18 +353,102 @@ impl<const LEN: usize> IWRRSelector<LEN> {
         LEN
     }
 
+    /// 判断待选择的权重数组是否为空
+    pub const fn is_empty(&self) -> bool {
+        LEN == 0
+    }
+
     /// 获取选择的当前轮数
-    pub fn round(&self) -> u8 {
+    pub fn round(&self) -> W {
         self.round
     }
 
     /// 获取最大的权重
-    pub fn max_weight(&self) -> u8 {
+    pub fn max_weight(&self) -> W {
         self.max_weight
     }
 
-    /// 尝试获取指定位置的权重
-    pub fn try_weight(&self, index: usize) -> Option<u8> {
+    /// 获取权重不为零的位置数量，为零时没有可以被选择的位置
+    pub fn eligible_len(&self) -> usize {
+        self.eligible
+    }
+
+    /// 获取改变权重的生效策略
+    pub fn change_policy(&self) -> ChangePolicy {
+        self.policy
+    }
+
+    /// 设置改变权重的生效策略，设置为立即生效时，未生效的权重会立即生效
+    pub fn set_change_policy(&mut self, policy: ChangePolicy) {
+        self.policy = policy;
+        if policy == ChangePolicy::Immediate {
+            self.apply_pending();
+        }
+    }
+
+    /// 判断是否按所有权重的最大公约数约简生效的权重
+    pub fn is_normalized(&self) -> bool {
+        self.normalized
+    }
+
+    /// 设置是否按所有权重的最大公约数约简生效的权重，约简后每个位置的份额不变，但周期更短，
+    /// 获取的权重仍然是未约简的权重，约简按改变权重的生效策略生效
+    pub fn set_normalized(&mut self, normalized: bool) {
+        if self.normalized == normalized {
+            return;
+        }
+
+        self.normalized = normalized;
+        self.changed = true;
+        if self.policy == ChangePolicy::Immediate {
+            self.apply_pending();
+        }
+    }
+
+    /// 判断指定位置是否可用，位置不存在则返回false
+    pub fn is_available(&self, index: usize) -> bool {
+        index < self.len() && self.available[index]
+    }
+
+    /// 设置指定位置是否可用，设置成功则返回指定位置是否可用的上个状态，
+    /// 不可用的位置在轮到时会被跳过，其它可用的位置仍然保持权重的比例
+    pub fn set_available(&mut self, index: usize, available: bool) -> Option<bool> {
+        if index >= self.len() {
+            return None;
+        }
+
+        let old = self.available[index];
+        if old && !available {
+            self.unavailable += 1;
+        } else if !old && available {
+            self.unavailable -= 1;
+        }
+        self.available[index] = available;
+
+        Some(old)
+    }
+
+    /// 获取不可用的位置数量
+    pub fn unavailable_len(&self) -> usize {
+        self.unavailable
+    }
+
+    /// 获取当前生效的权重数组，启用约简时为约简后的权重数组
+    pub fn effective_weights(&self) -> [W; LEN] {
+        self.weights
+    }
+
+    /// 尝试获取指定位置的权重，包括还未生效的权重
+    pub fn try_weight(&self, index: usize) -> Option<W> {
+        if index >= self.len() {
+            None
+        } else {
+            Some(self.pending[index])
+        }
+    }
+
+    /// 尝试获取指定位置当前生效的权重
+    pub fn try_active_weight(&self, index: usize) -> Option<W> {
         if index >= self.len() {
             None
         } else {
@@ -69,17 +459,39 @@ impl<const LEN: usize> IWRRSelector<LEN> {
     /// 改变指定位置的权重，改变成功则返回指定位置的上个权重
     pub fn change_weight(&mut self,
                          index: usize,
-                         weight: u8) -> Option<u8> {
-        if weight >= u8::MAX {
-            return None;
+                         weight: W) -> Option<W> {
+        self.try_change_weight(index, weight).ok()
+    }
+
+    /// 尝试改变指定位置的权重，改变成功则返回指定位置的上个权重，
+    /// 新的权重按改变权重的生效策略生效，指定位置超出范围或权重无效则返回错误
+    pub fn try_change_weight(&mut self,
+                             index: usize,
+                             weight: W) -> Result<W, WrrError> {
+        let old = self.try_weight(index).ok_or(WrrError::IndexOutOfRange {
+            index,
+            len: self.len(),
+        })?;
+
+        if !weight.is_valid() {
+            return Err(WrrError::InvalidWeight {
+                index,
+                weight: weight.as_u64(),
+            });
         }
 
-        if let Some(old) = self.try_weight(index) {
-            self.weights[index] = weight;
-            Some(old)
+        self.pending[index] = weight;
+        if self.policy == ChangePolicy::Immediate && self.normalized {
+            //权重的最大公约数可能改变，则重新约简所有权重
+            self.changed = true;
+            self.apply_pending();
+        } else if self.policy == ChangePolicy::Immediate {
+            self.apply_weight(index, weight);
         } else {
-            None
+            self.changed = true;
         }
+
+        Ok(old)
     }
 
     /// 获取当前选择的位置
@@ -87,157 +499,568 @@ impl<const LEN: usize> IWRRSelector<LEN> {
         self.pos
     }
 
-    /// 根据权重选择，并返回被选择的位置
+    /// 获取一个选择周期的长度，即所有生效权重的总和，启用约简时为约简后的权重的总和，
+    /// 超出usize的范围则为usize::MAX
+    pub fn cycle_len(&self) -> usize {
+        self.total_weight().min(usize::MAX as u128) as usize
+    }
+
+    /// 获取从周期的开始的第n次选择的位置，n从0开始且可以超过周期的长度，所有权重都为零则panic，
+    /// 不会改变选择器的状态，时间复杂度为O(LEN * log(max_weight))
+    pub fn nth_in_cycle(&self, n: usize) -> usize {
+        let cycle_len = self.total_weight();
+        if cycle_len == 0 {
+            panic!("Locate failed, len: {}, reason: all weights are zero",
+                   self.len());
+        }
+
+        self.locate(n as u128 % cycle_len).0
+    }
+
+    /// 定位到从周期的开始的第n次选择，下一次选择将返回第n次选择的位置，未生效的权重会立即生效
+    pub fn seek(&mut self, n: usize) {
+        self.reset();
+
+        let cycle_len = self.total_weight();
+        if cycle_len == 0 {
+            //没有可以被选择的位置
+            return;
+        }
+
+        let n = n as u128 % cycle_len;
+        if n == 0 {
+            //定位到周期的开始
+            return;
+        }
+
+        //定位到第n - 1次选择之后
+        let (pos, round) = self.locate(n - 1);
+        self.pos = pos + 1;
+        self.round = round;
+    }
+
+    /// 根据权重选择，并返回被选择的位置，所有权重都为零或没有可用的位置则panic
     pub fn select(&mut self) -> usize {
+        if let Some(pos) = self.try_select() {
+            pos
+        } else {
+            self.select_failed()
+        }
+    }
+
+    /// 尝试根据权重选择，并返回被选择的位置，所有权重都为零或没有可用的位置则返回空
+    pub fn try_select(&mut self) -> Option<usize> {
+        self.try_select_where(|_| true)
+    }
+
+    /// 根据权重选择可用且满足指定条件的位置，并返回被选择的位置，
+    /// 所有权重都为零或没有可用且满足条件的位置则panic
+    pub fn select_where<F>(&mut self, available: F) -> usize
+        where F: FnMut(usize) -> bool {
+        if let Some(pos) = self.try_select_where(available) {
+            pos
+        } else {
+            self.select_failed()
+        }
+    }
+
+    /// 尝试根据权重选择可用且满足指定条件的位置，并返回被选择的位置，
+    /// 轮到不满足条件的位置时会跳过，而不是重新选择，所以满足条件的位置之间仍然保持权重的比例，
+    /// 所有权重都为零或没有可用且满足条件的位置则返回空，并从新的周期开始下一次选择
+    pub fn try_select_where<F>(&mut self, mut available: F) -> Option<usize>
+        where F: FnMut(usize) -> bool {
+        if self.eligible == 0 {
+            //当前没有可以被选择的位置，则让未生效的权重立即生效
+            self.apply_pending();
+        }
+
+        //当前轮是否从第一个位置开始选择，以及当前轮中被跳过但在之后的轮中还可能被选择的最大权重
+        let mut whole = self.pos == 0;
+        let mut max_weight = W::ZERO;
         loop {
+            if self.eligible == 0 {
+                //没有可以被选择的位置
+                return None;
+            }
+
             for pos in self.pos..self.len() {
                 let weight = self.weights[pos];
-                if weight == 0 || weight < self.round {
+                if weight <= self.round {
                     //被忽略，则继续下一个位置的选择
                     self.pos += 1;
                     continue;
                 }
 
+                if self.skipping && self.skips[pos] > W::ZERO {
+                    //已被提前连续选择，则跳过，并继续下一个位置的选择
+                    self.skips[pos] = self.skips[pos] - W::ONE;
+                    self.pos += 1;
+                    if max_weight < weight {
+                        max_weight = weight;
+                    }
+                    continue;
+                }
+
+                if !self.available[pos] || !available(pos) {
+                    //不可用，则跳过，并继续下一个位置的选择
+                    self.pos += 1;
+                    continue;
+                }
+
                 //返回被选择的位置
                 self.pos += 1;
-                return pos;
+                return Some(pos);
             }
 
-            if self.round > self.max_weight {
+            let mut changed = false;
+            if self.policy == ChangePolicy::NextRound {
+                //完成当前轮的选择，则让未生效的权重生效
+                changed = self.changed;
+                self.apply_pending();
+            }
+
+            if whole && !changed && self.is_last_round(max_weight) {
+                //完整的一轮中没有可用的位置，且当前周期的之后的轮中也不会有，则提前完成当前周期的选择
+                let first = self.round == W::ZERO;
+                let changed = self.changed;
+                self.reset();
+                if first && !changed {
+                    //当前周期的第一轮中没有可用的位置
+                    return None;
+                }
+            } else if self.is_last_round(self.max_weight) {
                 //完成当前周期的选择，则重置选择器
                 self.reset();
             } else {
                 //完成当前轮的选择，则重置位置，并继续下一轮的选择
                 self.pos = 0;
-                self.round += 1;
+                self.round = self.round + W::ONE;
             }
+            whole = true;
+            max_weight = W::ZERO;
         }
     }
 
-    /// 重置选择器
+    /// 获取下一次选择的位置，不会改变选择器的状态，所有权重都为零则panic
+    pub fn peek(&self) -> usize {
+        if let Some(pos) = self.try_peek() {
+            pos
+        } else {
+            panic!("Peek failed, len: {}, reason: all weights are zero",
+                   self.len());
+        }
+    }
+
+    /// 尝试获取下一次选择的位置，不会改变选择器的状态，所有权重都为零则返回空
+    pub fn try_peek(&self) -> Option<usize> {
+        self.upcoming().next()
+    }
+
+    /// 获取从当前状态开始的后续所有选择的位置的迭代器，不会改变选择器的状态，
+    /// 迭代器使用选择器的副本选择，所以与之后的选择结果完全相同
+    pub fn upcoming(&self) -> impl Iterator<Item = usize> {
+        let mut selector = self.clone();
+        core::iter::from_fn(move || selector.try_select())
+    }
+
+    /// 根据权重选择，并返回被选择的位置和可以连续选择的次数，连续选择的次数不超过指定的最大连续次数，
+    /// 所有权重都为零则panic
+    pub fn select_run(&mut self, max_run: usize) -> (usize, usize) {
+        if let Some(run) = self.try_select_run(max_run) {
+            run
+        } else {
+            panic!("Select failed, len: {}, reason: all weights are zero",
+                   self.len());
+        }
+    }
+
+    /// 尝试根据权重选择，并返回被选择的位置和可以连续选择的次数，所有权重都为零则返回空，
+    /// 被选择的位置会提前连续选择它在当前周期中剩余的选择，但不超过指定的最大连续次数，
+    /// 之后的选择会跳过被提前选择的次数，所以每个周期中每个位置被选择的次数仍然与权重相同，
+    /// 最大连续次数为0时与最大连续次数为1相同
+    pub fn try_select_run(&mut self, max_run: usize) -> Option<(usize, usize)> {
+        let pos = self.try_select()?;
+
+        //被选择的位置在当前周期的之后轮中剩余的选择次数
+        let rest = self.weights[pos]
+            .as_u64()
+            .saturating_sub(self.round.as_u64() + 1);
+        let extra = rest.min(max_run.saturating_sub(1) as u64);
+        if extra > 0 {
+            self.skips[pos] = self.skips[pos] + W::from_u64(extra);
+            self.skipping = true;
+        }
+
+        Some((pos, extra as usize + 1))
+    }
+
+    /// 根据权重连续选择，并将被选择的位置依次写入指定的缓冲区，返回写入的数量，
+    /// 所有权重都为零时写入的数量小于缓冲区的长度
+    pub fn select_into(&mut self, buf: &mut [usize]) -> usize {
+        for (len, slot) in buf.iter_mut().enumerate() {
+            if let Some(pos) = self.try_select() {
+                *slot = pos;
+            } else {
+                return len;
+            }
+        }
+
+        buf.len()
+    }
+
+    /// 根据权重连续选择n次，并返回每个位置被选择的次数，选择后的状态与逐次选择n次完全相同，
+    /// 没有不可用的位置时，按周期直接计算而不需要逐次选择，时间复杂度为O(LEN * log(max_weight))，
+    /// 有未生效的权重或有被提前连续选择的位置时，最多逐次选择到当前周期结束
+    pub fn select_counts(&mut self, n: usize) -> [usize; LEN] {
+        let mut counts = [0; LEN];
+        if self.unavailable > 0 || self.eligible == 0 {
+            //有不可用的位置或当前没有可以被选择的位置，则逐次选择
+            for _ in 0..n {
+                if let Some(pos) = self.try_select() {
+                    counts[pos] += 1;
+                } else {
+                    break;
+                }
+            }
+
+            return counts;
+        }
+
+        //有未生效的权重或有被提前连续选择的位置，则逐次选择，直到都已生效，最多需要完成当前周期的选择
+        let mut n = n;
+        while n > 0 && (self.changed || self.skipping) {
+            if let Some(pos) = self.try_select() {
+                counts[pos] += 1;
+                n -= 1;
+            } else {
+                return counts;
+            }
+        }
+
+        if n == 0 || self.eligible == 0 {
+            return counts;
+        }
+
+        //计算从当前周期的开始到最后一次选择之后的选择次数，减去当前周期中已完成的选择次数
+        let cycle_len = self.total_weight();
+        let start = self.offset();
+        let end = start + n as u128;
+        let cycles = end / cycle_len - start / cycle_len;
+        let end_counts = self.prefix_counts(end % cycle_len);
+        let start_counts = self.prefix_counts(start % cycle_len);
+        for (pos, count) in counts.iter_mut().enumerate() {
+            let weight = self.weights[pos].as_u64() as u128;
+            *count += (cycles * weight + end_counts[pos] as u128 - start_counts[pos] as u128) as usize;
+        }
+
+        //定位到最后一次选择之后
+        let (pos, round) = self.locate((end - 1) % cycle_len);
+        self.pos = pos + 1;
+        self.round = round;
+
+        counts
+    }
+
+    /// 获取根据权重无限选择的迭代器，迭代器会改变选择器的状态，所有权重都为零时迭代结束
+    pub fn iter_mut(&mut self) -> SelectIter<'_, W, LEN> {
+        SelectIter {
+            selector: self,
+        }
+    }
+
+    /// 获取根据权重选择一个完整周期的迭代器，迭代器会改变选择器的状态，
+    /// 从任意状态开始连续选择一个周期，每个可用的位置被选择的次数都与权重相同，
+    /// 不可用的位置会被跳过，所以迭代的次数为所有可用的位置的生效权重的总和
+    pub fn take_cycle(&mut self) -> Take<SelectIter<'_, W, LEN>> {
+        let cycle_len = self.available_weight().min(usize::MAX as u128) as usize;
+        self.iter_mut().take(cycle_len)
+    }
+
+    /// 获取从周期的开始的一个完整周期的选择的位置，不会改变选择器的状态，
+    /// 未生效的权重会在周期的开始生效，不考虑位置是否可用，所以总是包含所有权重不为零的位置
+    #[cfg(feature = "alloc")]
+    pub fn schedule(&self) -> Vec<usize> {
+        let mut selector = self.clone();
+        selector.available = [true; LEN];
+        selector.unavailable = 0;
+        selector.reset();
+
+        let cycle_len = selector.cycle_len();
+        let mut schedule = Vec::with_capacity(cycle_len);
+        for _ in 0..cycle_len {
+            schedule.push(selector.select());
+        }
+
+        schedule
+    }
+
+    /// 重置选择器，未生效的权重会立即生效
     pub fn reset(&mut self) {
-        self.round = 0;
+        self.round = W::ZERO;
         self.pos = 0;
+        if self.skipping {
+            self.skips = [W::ZERO; LEN];
+            self.skipping = false;
+        }
+        self.apply_pending();
     }
-}
 
-///
-/// 交替加权轮询选择器
-///
-#[derive(Debug, Clone)]
-pub struct IWRRSelectorByWider<const LEN: usize> {
-    pos:        usize,          //当前选择的位置
-    round:      usize,          //选择的当前轮数
-    max_weight: usize,          //最大的权重
-    weights:    [usize; LEN],   //待选择的权重数组
-}
+    // 获取所有生效权重的总和
+    fn total_weight(&self) -> u128 {
+        self.weights
+            .iter()
+            .map(|weight| weight.as_u64() as u128)
+            .sum()
+    }
 
-impl<const LEN: usize> Default for IWRRSelectorByWider<LEN> {
-    /// 默认构建指定长度且权重相同的交替加权轮询选择器
-    fn default() -> Self {
-        Self::new([1; LEN])
+    // 获取所有可用的位置的生效权重的总和
+    fn available_weight(&self) -> u128 {
+        self.weights
+            .iter()
+            .zip(self.available.iter())
+            .filter(|(_, available)| **available)
+            .map(|(weight, _)| weight.as_u64() as u128)
+            .sum()
     }
-}
 
-impl<const LEN: usize> IWRRSelectorByWider<LEN> {
-    /// 构建指定待选择的权重数组的交替加权轮询选择器
-    pub fn new(weights: [usize; LEN]) -> Self {
-        let mut max_weight = 0;
-        for weight in weights {
-            if weight >= usize::MAX {
-                panic!("Create IWRRSelector failed, weight: {}, reason: invalid weight",
-                       weight);
+    // 获取从周期的开始的第n次选择的位置和轮数，n必须小于周期的长度
+    fn locate(&self, n: u128) -> (usize, W) {
+        //二分查找第n次选择所在的轮数，即前round + 1轮的选择次数大于n的最小轮数
+        let mut low = 0;
+        let mut high = self.max_weight.as_u64() - 1;
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.count_before(mid + 1) > n {
+                high = mid;
+            } else {
+                low = mid + 1;
             }
+        }
 
-            if max_weight < weight {
-                //替换最大的权重
-                max_weight = weight;
+        //在所在的轮中查找第n次选择的位置
+        let mut rest = n - self.count_before(low);
+        for (pos, weight) in self.weights.iter().enumerate() {
+            if weight.as_u64() > low {
+                if rest == 0 {
+                    return (pos, W::from_u64(low));
+                }
+                rest -= 1;
             }
         }
 
-        IWRRSelectorByWider {
-            round: 0,
-            max_weight,
-            pos: 0,
-            weights,
-        }
+        unreachable!()
     }
 
-    /// 获取待选择的权重数组的长度
-    pub const fn len(&self) -> usize {
-        LEN
+    // 获取当前周期中已完成的选择次数
+    fn offset(&self) -> u128 {
+        let round = self.round.as_u64();
+        let selected = self.weights[..self.pos.min(self.len())]
+            .iter()
+            .filter(|weight| weight.as_u64() > round)
+            .count();
+
+        self.count_before(round) + selected as u128
     }
 
-    /// 获取选择的当前轮数
-    pub fn round(&self) -> usize {
-        self.round
+    // 获取从周期的开始的前n次选择中每个位置被选择的次数，n必须小于周期的长度
+    fn prefix_counts(&self, n: u128) -> [usize; LEN] {
+        let mut counts = [0; LEN];
+        if n == 0 {
+            return counts;
+        }
+
+        //前n次选择包括之前所有轮的选择，以及所在轮中直到第n - 1次选择的位置的选择
+        let (last, round) = self.locate(n - 1);
+        let round = round.as_u64();
+        for (pos, (count, weight)) in counts.iter_mut().zip(self.weights.iter()).enumerate() {
+            let weight = weight.as_u64();
+            *count = weight.min(round) as usize;
+            if weight > round && pos <= last {
+                *count += 1;
+            }
+        }
+
+        counts
     }
 
-    /// 获取最大的权重
-    pub fn max_weight(&self) -> usize {
-        self.max_weight
+    // 获取周期中前指定轮数的选择次数
+    fn count_before(&self, round: u64) -> u128 {
+        self.weights
+            .iter()
+            .map(|weight| weight.as_u64().min(round) as u128)
+            .sum()
     }
 
-    /// 尝试获取指定位置的权重
-    pub fn try_weight(&self, index: usize) -> Option<usize> {
-        if index >= self.len() {
-            None
+    // 判断当前轮是否是指定最大权重的最后一轮或已超过最后一轮，不计算轮数加一，避免溢出
+    fn is_last_round(&self, max_weight: W) -> bool {
+        max_weight <= self.round || max_weight - self.round == W::ONE
+    }
+
+    // 选择失败，则panic
+    fn select_failed(&self) -> ! {
+        if self.eligible == 0 {
+            panic!("Select failed, len: {}, reason: all weights are zero",
+                   self.len());
         } else {
-            Some(self.weights[index])
+            panic!("Select failed, len: {}, reason: no available slot",
+                   self.len());
         }
     }
 
-    /// 改变指定位置的权重，改变成功则返回指定位置的上个权重
-    pub fn change_weight(&mut self,
-                         index: usize,
-                         weight: usize) -> Option<usize> {
-        if weight >= usize::MAX {
-            return None;
+    // 让指定位置的权重生效，并保持最大的权重和权重不为零的位置数量准确
+    fn apply_weight(&mut self, index: usize, weight: W) {
+        let old = self.weights[index];
+        self.weights[index] = weight;
+
+        if old == W::ZERO && weight > W::ZERO {
+            self.eligible += 1;
+        } else if old > W::ZERO && weight == W::ZERO {
+            self.eligible -= 1;
         }
 
-        if let Some(old) = self.try_weight(index) {
-            self.weights[index] = weight;
-            Some(old)
-        } else {
-            None
+        if weight > self.max_weight {
+            //替换最大的权重
+            self.max_weight = weight;
+        } else if old == self.max_weight && weight < old {
+            //减少了最大的权重，则重新计算最大的权重
+            self.max_weight = self.weights.iter().copied().max().unwrap_or(W::ZERO);
         }
+        self.clamp_skip(index);
     }
 
-    /// 获取当前选择的位置
-    pub fn pos(&self) -> usize {
-        self.pos
+    // 限制指定位置需要跳过的次数不超过它在当前周期中剩余的选择次数，避免减少权重后跳过的次数超过权重
+    fn clamp_skip(&mut self, index: usize) {
+        if !self.skipping {
+            return;
+        }
+
+        let mut rest = self.weights[index]
+            .as_u64()
+            .saturating_sub(self.round.as_u64());
+        if index < self.pos {
+            //已在当前轮中被访问
+            rest = rest.saturating_sub(1);
+        }
+        if self.skips[index].as_u64() > rest {
+            self.skips[index] = W::from_u64(rest);
+        }
     }
 
-    /// 根据权重选择，并返回被选择的位置
-    pub fn select(&mut self) -> usize {
-        loop {
-            for pos in self.pos..self.len() {
-                let weight = self.weights[pos];
-                if weight == 0 || weight < self.round {
-                    //被忽略，则继续下一个位置的选择
-                    self.pos += 1;
-                    continue;
-                }
+    // 让所有未生效的权重生效
+    fn apply_pending(&mut self) {
+        if !self.changed {
+            return;
+        }
 
-                //返回被选择的位置
-                self.pos += 1;
-                return pos;
+        let weights = Self::effective(&self.pending, self.normalized);
+        let mut max_weight = W::ZERO;
+        let mut eligible = 0;
+        for weight in weights {
+            if weight > W::ZERO {
+                eligible += 1;
             }
 
-            if self.round > self.max_weight {
-                //完成当前周期的选择，则重置选择器
-                self.reset();
-            } else {
-                //完成当前轮的选择，则重置位置，并继续下一轮的选择
-                self.pos = 0;
-                self.round += 1;
+            if max_weight < weight {
+                max_weight = weight;
             }
         }
+
+        self.weights = weights;
+        self.max_weight = max_weight;
+        self.eligible = eligible;
+        self.changed = false;
+        for index in 0..LEN {
+            self.clamp_skip(index);
+        }
     }
 
-    /// 重置选择器
-    pub fn reset(&mut self) {
-        self.round = 0;
-        self.pos = 0;
+    // 获取指定权重数组生效后的权重数组，需要约简时所有权重除以它们的最大公约数
+    fn effective(weights: &[W; LEN], normalized: bool) -> [W; LEN] {
+        if !normalized {
+            return *weights;
+        }
+
+        let divisor = weights
+            .iter()
+            .fold(0, |divisor, weight| gcd(divisor, weight.as_u64()));
+        if divisor <= 1 {
+            //所有权重都为零或已经是最简的
+            return *weights;
+        }
+
+        weights.map(|weight| W::from_u64(weight.as_u64() / divisor))
     }
-}
\ No newline at end of file
+}
+
+// 获取两个整数的最大公约数，与零的最大公约数为另一个整数
+#[inline]
+fn gcd(mut x: u64, mut y: u64) -> u64 {
+    while y != 0 {
+        let z = x % y;
+        x = y;
+        y = z;
+    }
+
+    x
+}
+
+impl<W: Weight, const LEN: usize> IntoIterator for GenericIWRRSelector<W, LEN> {
+    type Item = usize;
+    type IntoIter = IntoSelectIter<W, LEN>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoSelectIter {
+            selector: self,
+        }
+    }
+}
+
+impl<'a, W: Weight, const LEN: usize> IntoIterator for &'a mut GenericIWRRSelector<W, LEN> {
+    type Item = usize;
+    type IntoIter = SelectIter<'a, W, LEN>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+///
+/// 根据权重无限选择的迭代器，所有权重都为零时迭代结束
+///
+#[derive(Debug)]
+pub struct SelectIter<'a, W: Weight, const LEN: usize> {
+    selector: &'a mut GenericIWRRSelector<W, LEN>,  //选择器
+}
+
+impl<'a, W: Weight, const LEN: usize> Iterator for SelectIter<'a, W, LEN> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.selector.try_select()
+    }
+}
+
+///
+/// 持有选择器，并根据权重无限选择的迭代器，所有权重都为零时迭代结束
+///
+#[derive(Debug, Clone)]
+pub struct IntoSelectIter<W: Weight, const LEN: usize> {
+    selector: GenericIWRRSelector<W, LEN>,  //选择器
+}
+
+impl<W: Weight, const LEN: usize> IntoSelectIter<W, LEN> {
+    /// 获取持有的选择器
+    pub fn into_inner(self) -> GenericIWRRSelector<W, LEN> {
+        self.selector
+    }
+}
+
+impl<W: Weight, const LEN: usize> Iterator for IntoSelectIter<W, LEN> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.selector.try_select()
+    }
+}
@@ -0,0 +1,149 @@
+use alloc::vec::Vec;
+
+use crate::{Weight, WrrError, SlotId, GenericDynIWRRSelector};
+
+///
+/// 加权池，权重类型为u8
+///
+pub type WeightedPool<T> = GenericWeightedPool<T, u8>;
+
+///
+/// 指定权重类型的加权池，同时持有待选择的成员和成员的权重，并按交替加权轮询选择成员
+///
+#[derive(Debug, Clone)]
+pub struct GenericWeightedPool<T, W: Weight> {
+    selector:   GenericDynIWRRSelector<W>,  //成员的选择器
+    items:      Vec<Option<T>>,             //以位置的下标索引的成员数组
+}
+
+impl<T, W: Weight> Default for GenericWeightedPool<T, W> {
+    /// 默认构建空的加权池
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, W: Weight> GenericWeightedPool<T, W> {
+    /// 构建空的加权池
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// 构建指定成员容量的加权池
+    pub fn with_capacity(capacity: usize) -> Self {
+        GenericWeightedPool {
+            selector: GenericDynIWRRSelector::with_capacity(capacity),
+            items: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// 获取成员的数量
+    pub fn len(&self) -> usize {
+        self.selector.len()
+    }
+
+    /// 判断是否没有成员
+    pub fn is_empty(&self) -> bool {
+        self.selector.is_empty()
+    }
+
+    /// 判断指定成员是否存在
+    pub fn contains(&self, id: SlotId) -> bool {
+        self.selector.contains(id)
+    }
+
+    /// 增加指定权重的成员，并返回成员的唯一标识，权重无效则立即panic
+    pub fn insert(&mut self, item: T, weight: W) -> SlotId {
+        match self.try_insert(item, weight) {
+            Err(e) => panic!("Insert item failed, reason: {}", e),
+            Ok(id) => id,
+        }
+    }
+
+    /// 尝试增加指定权重的成员，并返回成员的唯一标识，权重无效则返回错误
+    pub fn try_insert(&mut self, item: T, weight: W) -> Result<SlotId, WrrError> {
+        let id = self.selector.try_push(weight)?;
+        if id.index() < self.items.len() {
+            //复用已被移除成员的下标
+            self.items[id.index()] = Some(item);
+        } else {
+            self.items.push(Some(item));
+        }
+
+        Ok(id)
+    }
+
+    /// 移除指定成员，移除成功则返回指定成员
+    pub fn remove(&mut self, id: SlotId) -> Option<T> {
+        self.selector.remove(id)?;
+        self.items[id.index()].take()
+    }
+
+    /// 获取指定成员的只读引用
+    pub fn get(&self, id: SlotId) -> Option<&T> {
+        if self.selector.contains(id) {
+            self.items[id.index()].as_ref()
+        } else {
+            None
+        }
+    }
+
+    /// 获取指定成员的可写引用
+    pub fn get_mut(&mut self, id: SlotId) -> Option<&mut T> {
+        if self.selector.contains(id) {
+            self.items[id.index()].as_mut()
+        } else {
+            None
+        }
+    }
+
+    /// 获取指定成员的权重
+    pub fn get_weight(&self, id: SlotId) -> Option<W> {
+        self.selector.try_weight(id)
+    }
+
+    /// 设置指定成员的权重，设置成功则返回指定成员的上个权重
+    pub fn set_weight(&mut self, id: SlotId, weight: W) -> Option<W> {
+        self.selector.change_weight(id, weight)
+    }
+
+    /// 根据权重选择，并返回被选择成员的只读引用，没有权重不为零的成员则返回空
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<&T> {
+        self.next_with_id().map(|(_, item)| item)
+    }
+
+    /// 根据权重选择，并返回被选择成员的可写引用，没有权重不为零的成员则返回空
+    pub fn next_mut(&mut self) -> Option<&mut T> {
+        let id = self.selector.try_select()?;
+        self.items[id.index()].as_mut()
+    }
+
+    /// 根据权重选择，并返回被选择成员的唯一标识和只读引用，没有权重不为零的成员则返回空
+    pub fn next_with_id(&mut self) -> Option<(SlotId, &T)> {
+        let id = self.selector.try_select()?;
+        self.items[id.index()].as_ref().map(|item| (id, item))
+    }
+
+    /// 获取所有成员的唯一标识、只读引用和权重的迭代器
+    pub fn iter(&self) -> impl Iterator<Item = (SlotId, &T, W)> + '_ {
+        //成员数组与选择器中的位置一一对应，且都按位置的下标排列
+        self.selector
+            .iter()
+            .zip(self.items.iter().flatten())
+            .map(|((id, weight), item)| (id, item, weight))
+    }
+
+    /// 获取所有成员的唯一标识、可写引用和权重的迭代器
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (SlotId, &mut T, W)> + '_ {
+        self.selector
+            .iter()
+            .zip(self.items.iter_mut().flatten())
+            .map(|((id, weight), item)| (id, item, weight))
+    }
+
+    /// 重置成员的选择器
+    pub fn reset(&mut self) {
+        self.selector.reset();
+    }
+}
@@ -0,0 +1,150 @@
+use alloc::collections::VecDeque;
+
+use crate::{Weight, WrrError, GenericIWRRSelector};
+
+///
+/// 加权多队列，权重类型为u8
+///
+pub type WeightedQueues<T, const LEN: usize> = GenericWeightedQueues<T, u8, LEN>;
+
+///
+/// 指定权重类型的加权多队列，每个类别有一个先进先出的队列，并按交替加权轮询从各个队列中弹出成员
+///
+#[derive(Debug, Clone)]
+pub struct GenericWeightedQueues<T, W: Weight, const LEN: usize> {
+    selector:   GenericIWRRSelector<W, LEN>,    //类别的选择器，空队列的类别不可用
+    len:        usize,                          //所有队列的成员数量
+    queues:     [VecDeque<T>; LEN],             //以类别索引的队列数组
+}
+
+impl<T, W: Weight, const LEN: usize> Default for GenericWeightedQueues<T, W, LEN> {
+    /// 默认构建指定类别数量且权重相同的加权多队列
+    fn default() -> Self {
+        Self::new([W::ONE; LEN])
+    }
+}
+
+impl<T, W: Weight, const LEN: usize> GenericWeightedQueues<T, W, LEN> {
+    /// 构建指定类别权重数组的加权多队列，权重数组无效则立即panic
+    pub fn new(weights: [W; LEN]) -> Self {
+        match Self::try_new(weights) {
+            Err(e) => panic!("Create WeightedQueues failed, reason: {}", e),
+            Ok(queues) => queues,
+        }
+    }
+
+    /// 尝试构建指定类别权重数组的加权多队列，
+    /// 权重数组为空、包含无效的权重或所有权重都为零则返回错误
+    pub fn try_new(weights: [W; LEN]) -> Result<Self, WrrError> {
+        let mut selector = GenericIWRRSelector::try_new(weights)?;
+        for class in 0..LEN {
+            //所有队列都为空
+            selector.set_available(class, false);
+        }
+
+        Ok(GenericWeightedQueues {
+            selector,
+            len: 0,
+            queues: core::array::from_fn(|_| VecDeque::new()),
+        })
+    }
+
+    /// 获取类别的数量
+    pub const fn classes(&self) -> usize {
+        LEN
+    }
+
+    /// 获取所有队列的成员数量
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// 判断所有队列是否都为空
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 获取指定类别的队列的成员数量，类别不存在则返回0
+    pub fn len_of(&self, class: usize) -> usize {
+        self.queues.get(class).map_or(0, VecDeque::len)
+    }
+
+    /// 尝试获取指定类别的权重
+    pub fn try_weight(&self, class: usize) -> Option<W> {
+        self.selector.try_weight(class)
+    }
+
+    /// 改变指定类别的权重，改变成功则返回指定类别的上个权重
+    pub fn change_weight(&mut self,
+                         class: usize,
+                         weight: W) -> Option<W> {
+        self.selector.change_weight(class, weight)
+    }
+
+    /// 在指定类别的队列尾部增加成员，类别不存在则立即panic
+    pub fn push(&mut self, class: usize, item: T) {
+        if let Err(e) = self.try_push(class, item) {
+            panic!("Push item failed, reason: {}", e);
+        }
+    }
+
+    /// 尝试在指定类别的队列尾部增加成员，类别不存在则返回错误
+    pub fn try_push(&mut self, class: usize, item: T) -> Result<(), WrrError> {
+        let queue = self.queues.get_mut(class).ok_or(WrrError::IndexOutOfRange {
+            index: class,
+            len: LEN,
+        })?;
+
+        if queue.is_empty() {
+            //队列不再为空，则类别可以被选择
+            self.selector.set_available(class, true);
+        }
+        queue.push_back(item);
+        self.len += 1;
+
+        Ok(())
+    }
+
+    /// 根据权重从不为空的队列的头部弹出成员，并返回成员的类别和成员，
+    /// 空队列的类别会被跳过，不为空的队列之间仍然保持权重的比例，
+    /// 所有队列都为空或不为空的队列的权重都为零则返回空
+    pub fn pop(&mut self) -> Option<(usize, T)> {
+        if self.len == 0 {
+            //所有队列都为空
+            return None;
+        }
+
+        let class = self.selector.try_select()?;
+        let queue = &mut self.queues[class];
+        let item = queue.pop_front()?;
+        if queue.is_empty() {
+            //队列已为空，则类别不可以被选择
+            self.selector.set_available(class, false);
+        }
+        self.len -= 1;
+
+        Some((class, item))
+    }
+
+    /// 从指定类别的队列的头部弹出成员，不改变选择的状态
+    pub fn pop_of(&mut self, class: usize) -> Option<T> {
+        let queue = self.queues.get_mut(class)?;
+        let item = queue.pop_front()?;
+        if queue.is_empty() {
+            self.selector.set_available(class, false);
+        }
+        self.len -= 1;
+
+        Some(item)
+    }
+
+    /// 清空所有队列，并重置选择器
+    pub fn clear(&mut self) {
+        for (class, queue) in self.queues.iter_mut().enumerate() {
+            queue.clear();
+            self.selector.set_available(class, false);
+        }
+        self.len = 0;
+        self.selector.reset();
+    }
+}
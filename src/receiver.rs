@@ -0,0 +1,208 @@
+use std::sync::mpsc::{Receiver, RecvError, TryRecvError, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use crate::{Weight, WrrError, GenericIWRRSelector};
+
+/// 阻塞接收时在一个通道上等待的最短时间
+const MIN_WAIT: Duration = Duration::from_micros(10);
+
+/// 阻塞接收时在一个通道上等待的最长时间
+const MAX_WAIT: Duration = Duration::from_millis(1);
+
+///
+/// 加权接收器，权重类型为u8
+///
+pub type WeightedReceiver<T, const LEN: usize> = GenericWeightedReceiver<T, u8, LEN>;
+
+///
+/// 指定权重类型的加权接收器，合并多个通道的接收端，并按交替加权轮询选择下一个接收消息的通道，
+/// 空的、已断开的或权重为零的通道会被跳过，所有权重不为零的通道都已断开时接收失败，
+/// 标准库的通道不能同时等待多个接收端，所以有多个可以等待的通道时，阻塞接收会轮流在每个通道上等待10微秒到1毫秒，
+/// 空闲时每秒最多会被唤醒约1000次，其它通道的消息最多延迟1毫秒才会被接收，只有一个可以等待的通道时才会一直阻塞
+///
+#[derive(Debug)]
+pub struct GenericWeightedReceiver<T, W: Weight, const LEN: usize> {
+    selector:       GenericIWRRSelector<W, LEN>,    //通道的选择器
+    disconnected:   [bool; LEN],                    //以通道索引的通道是否已断开
+    buffered:       [Option<T>; LEN],               //以通道索引的已接收但还未被选择的消息
+    receivers:      [Receiver<T>; LEN],             //以通道索引的接收端数组
+}
+
+impl<T, W: Weight, const LEN: usize> GenericWeightedReceiver<T, W, LEN> {
+    /// 构建指定接收端数组和通道权重数组的加权接收器，权重数组无效则立即panic
+    pub fn new(receivers: [Receiver<T>; LEN], weights: [W; LEN]) -> Self {
+        match Self::try_new(receivers, weights) {
+            Err(e) => panic!("Create WeightedReceiver failed, reason: {}", e),
+            Ok(receiver) => receiver,
+        }
+    }
+
+    /// 尝试构建指定接收端数组和通道权重数组的加权接收器，
+    /// 权重数组为空、包含无效的权重或所有权重都为零则返回错误
+    pub fn try_new(receivers: [Receiver<T>; LEN],
+                   weights: [W; LEN]) -> Result<Self, WrrError> {
+        let selector = GenericIWRRSelector::try_new(weights)?;
+
+        Ok(GenericWeightedReceiver {
+            selector,
+            disconnected: [false; LEN],
+            buffered: core::array::from_fn(|_| None),
+            receivers,
+        })
+    }
+
+    /// 获取通道的数量
+    pub const fn len(&self) -> usize {
+        LEN
+    }
+
+    /// 判断通道的数量是否为零
+    pub const fn is_empty(&self) -> bool {
+        LEN == 0
+    }
+
+    /// 获取还可以接收消息的通道数量，即权重不为零且未断开的通道数量，
+    /// 通道在所有消息都被接收后才会被认为已断开，权重为零的通道在改变权重前不会被接收，所以不被计入
+    pub fn connected_len(&self) -> usize {
+        (0..LEN)
+            .filter(|index| self.is_active(*index) && !self.disconnected[*index])
+            .count()
+    }
+
+    /// 判断指定通道是否已断开，通道不存在则返回空
+    pub fn is_disconnected(&self, index: usize) -> Option<bool> {
+        self.disconnected.get(index).copied()
+    }
+
+    /// 尝试获取指定通道的权重
+    pub fn try_weight(&self, index: usize) -> Option<W> {
+        self.selector.try_weight(index)
+    }
+
+    /// 改变指定通道的权重，改变成功则返回指定通道的上个权重
+    pub fn change_weight(&mut self,
+                         index: usize,
+                         weight: W) -> Option<W> {
+        self.selector.change_weight(index, weight)
+    }
+
+    /// 尝试根据权重从有消息的通道接收消息，并返回通道的索引和消息，不会阻塞，
+    /// 没有可以接收的消息则返回空错误，所有权重不为零的通道都已断开则返回断开错误
+    pub fn try_recv(&mut self) -> Result<(usize, T), TryRecvError> {
+        self.poll();
+
+        let buffered = &self.buffered;
+        if let Some(index) = self.selector.try_select_where(|index| buffered[index].is_some()) {
+            if let Some(msg) = self.buffered[index].take() {
+                return Ok((index, msg));
+            }
+        }
+
+        if self.connected_len() == 0 {
+            //所有权重不为零的通道都已断开，且没有可以被选择的已接收的消息
+            Err(TryRecvError::Disconnected)
+        } else {
+            Err(TryRecvError::Empty)
+        }
+    }
+
+    /// 根据权重从有消息的通道接收消息，并返回通道的索引和消息，没有可以接收的消息则阻塞，
+    /// 所有通道都已断开则返回错误，有多个可以等待的通道时，阻塞期间会周期性的唤醒，且接收有最多1毫秒的延迟
+    pub fn recv(&mut self) -> Result<(usize, T), RecvError> {
+        self.recv_deadline(None).map_err(|_| RecvError)
+    }
+
+    /// 根据权重从有消息的通道接收消息，并返回通道的索引和消息，没有可以接收的消息则阻塞，
+    /// 超时则返回超时错误，所有通道都已断开则返回断开错误，阻塞期间的唤醒和接收的延迟与recv相同
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<(usize, T), RecvTimeoutError> {
+        //超时时间无法表示，则一直等待
+        self.recv_deadline(Instant::now().checked_add(timeout))
+    }
+
+    // 根据权重接收消息，直到指定的截止时间，没有截止时间则一直等待
+    fn recv_deadline(&mut self,
+                     deadline: Option<Instant>) -> Result<(usize, T), RecvTimeoutError> {
+        //标准库的通道不能同时等待多个接收端，所以轮流在一个通道上短暂的阻塞等待
+        let mut wait = MIN_WAIT;
+        let mut index = 0;
+        loop {
+            match self.try_recv() {
+                Ok(msg) => return Ok(msg),
+                Err(TryRecvError::Disconnected) => return Err(RecvTimeoutError::Disconnected),
+                Err(TryRecvError::Empty) => (),
+            }
+
+            let mut remaining = None;
+            if let Some(deadline) = deadline {
+                let now = Instant::now();
+                if now >= deadline {
+                    return Err(RecvTimeoutError::Timeout);
+                }
+                remaining = Some(deadline - now);
+            }
+            let timeout = remaining.map_or(wait, |remaining| wait.min(remaining));
+
+            //从上次等待的通道之后，选择权重不为零、未断开且没有已接收的消息的通道等待
+            let mut candidates = (0..LEN)
+                .map(|offset| (index + offset) % LEN)
+                .filter(|index| self.is_waitable(*index));
+            let next = candidates.next();
+            let only = candidates.next().is_none();
+            match next {
+                None => {
+                    //没有可以接收消息的通道
+                    return Err(RecvTimeoutError::Disconnected);
+                },
+                Some(next) => {
+                    //只有一个可以等待的通道，则在这个通道上一直阻塞等待，直到截止时间
+                    let result = match (only, remaining) {
+                        (true, None) => {
+                            self.receivers[next]
+                                .recv()
+                                .map_err(|_| RecvTimeoutError::Disconnected)
+                        },
+                        (true, Some(remaining)) => self.receivers[next].recv_timeout(remaining),
+                        (false, _) => self.receivers[next].recv_timeout(timeout),
+                    };
+                    match result {
+                        Ok(msg) => self.buffered[next] = Some(msg),
+                        Err(RecvTimeoutError::Disconnected) => self.disconnect(next),
+                        Err(RecvTimeoutError::Timeout) => (),
+                    }
+                    index = next + 1;
+                },
+            }
+            wait = (wait * 2).min(MAX_WAIT);
+        }
+    }
+
+    // 从所有权重不为零、未断开且没有已接收的消息的通道尝试接收消息
+    fn poll(&mut self) {
+        for index in 0..LEN {
+            if !self.is_waitable(index) {
+                continue;
+            }
+
+            match self.receivers[index].try_recv() {
+                Ok(msg) => self.buffered[index] = Some(msg),
+                Err(TryRecvError::Disconnected) => self.disconnect(index),
+                Err(TryRecvError::Empty) => (),
+            }
+        }
+    }
+
+    // 判断指定通道的权重是否不为零，权重为零的通道的消息不会被选择，所以也不会被接收
+    fn is_active(&self, index: usize) -> bool {
+        self.selector.try_active_weight(index) > Some(W::ZERO)
+    }
+
+    // 判断是否可以从指定通道接收消息
+    fn is_waitable(&self, index: usize) -> bool {
+        self.is_active(index) && !self.disconnected[index] && self.buffered[index].is_none()
+    }
+
+    // 标记指定通道已断开
+    fn disconnect(&mut self, index: usize) {
+        self.disconnected[index] = true;
+    }
+}
@@ -0,0 +1,254 @@
+use std::fmt;
+use std::pin::Pin;
+use std::boxed::Box;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::future::Future;
+use std::task::{Context, Poll, Wake, Waker};
+
+use crate::{Weight, WrrError, GenericIWRRSelector};
+
+///
+/// 固定在堆上的future，不要求可以在线程间移动
+///
+pub type LocalBoxFuture<T> = Pin<Box<dyn Future<Output = T>>>;
+
+///
+/// 加权选择的future，权重类型为u8
+///
+pub type WeightedSelect<T, const LEN: usize> = GenericWeightedSelect<T, u8, LEN>;
+
+///
+/// 指定权重类型的加权选择的future，同时等待多个子future，每次被唤醒时按交替加权轮询的顺序轮询已就绪的子future，
+/// 任意子future完成时返回子future的索引和输出，之后可以继续等待其它子future，所有子future都已完成时返回空，
+/// 权重为零的子future不会被轮询
+///
+pub struct GenericWeightedSelect<T, W: Weight, const LEN: usize> {
+    selector:   GenericIWRRSelector<W, LEN>,         //子future的选择器
+    len:        usize,                               //未完成的子future数量
+    shared:     Arc<Shared<LEN>>,                    //与子future的唤醒器共享的就绪状态
+    wakers:     [Waker; LEN],                        //每个子future的唤醒器
+    futures:    [Option<LocalBoxFuture<T>>; LEN],    //待轮询的子future数组，已完成的子future为空
+}
+
+impl<T, W: Weight, const LEN: usize> fmt::Debug for GenericWeightedSelect<T, W, LEN> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WeightedSelect")
+            .field("len", &self.len)
+            .field("selector", &self.selector)
+            .finish()
+    }
+}
+
+// 子future都已被固定在堆上，其它字段不会被固定，所以可以安全的移动
+impl<T, W: Weight, const LEN: usize> Unpin for GenericWeightedSelect<T, W, LEN> {}
+
+impl<T, W: Weight, const LEN: usize> GenericWeightedSelect<T, W, LEN> {
+    /// 构建指定子future数组和权重数组的加权选择的future，权重数组无效则立即panic
+    pub fn new(futures: [LocalBoxFuture<T>; LEN], weights: [W; LEN]) -> Self {
+        match Self::try_new(futures, weights) {
+            Err(e) => panic!("Create WeightedSelect failed, reason: {}", e),
+            Ok(select) => select,
+        }
+    }
+
+    /// 尝试构建指定子future数组和权重数组的加权选择的future，
+    /// 权重数组为空、包含无效的权重或所有权重都为零则返回错误
+    pub fn try_new(futures: [LocalBoxFuture<T>; LEN],
+                   weights: [W; LEN]) -> Result<Self, WrrError> {
+        let selector = GenericIWRRSelector::try_new(weights)?;
+
+        //所有子future在首次轮询前都是就绪的
+        let shared = Arc::new(Shared {
+            waker: Mutex::new(None),
+            polling: AtomicBool::new(false),
+            readies: core::array::from_fn(|_| AtomicBool::new(true)),
+        });
+        let wakers = core::array::from_fn(|index| {
+            Waker::from(Arc::new(ChildWaker {
+                index,
+                shared: shared.clone(),
+            }))
+        });
+
+        Ok(GenericWeightedSelect {
+            selector,
+            len: LEN,
+            shared,
+            wakers,
+            futures: futures.map(Some),
+        })
+    }
+
+    /// 获取未完成的子future数量
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// 判断所有子future是否都已完成
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 判断指定子future是否已完成，子future不存在则返回空
+    pub fn is_terminated(&self, index: usize) -> Option<bool> {
+        self.futures.get(index).map(Option::is_none)
+    }
+
+    /// 尝试获取指定子future的权重
+    pub fn try_weight(&self, index: usize) -> Option<W> {
+        self.selector.try_weight(index)
+    }
+
+    /// 改变指定子future的权重，改变成功则返回指定子future的上个权重
+    pub fn change_weight(&mut self,
+                         index: usize,
+                         weight: W) -> Option<W> {
+        self.selector.change_weight(index, weight)
+    }
+
+    /// 替换指定索引的子future，并返回未完成的上个子future，索引不存在则立即panic
+    pub fn replace(&mut self,
+                   index: usize,
+                   future: LocalBoxFuture<T>) -> Option<LocalBoxFuture<T>> {
+        match self.try_replace(index, future) {
+            Err(e) => panic!("Replace future failed, reason: {}", e),
+            Ok(old) => old,
+        }
+    }
+
+    /// 尝试替换指定索引的子future，并返回未完成的上个子future，索引不存在则返回错误，
+    /// 新的子future在下次轮询时是就绪的
+    pub fn try_replace(&mut self,
+                       index: usize,
+                       future: LocalBoxFuture<T>) -> Result<Option<LocalBoxFuture<T>>, WrrError> {
+        let slot = self.futures.get_mut(index).ok_or(WrrError::IndexOutOfRange {
+            index,
+            len: LEN,
+        })?;
+
+        let old = slot.replace(future);
+        if old.is_none() {
+            self.len += 1;
+        }
+        self.shared.readies[index].store(true, Ordering::SeqCst);
+
+        Ok(old)
+    }
+
+    // 判断是否有可以被轮询的已就绪的子future
+    fn has_ready(&self) -> bool {
+        (0..LEN).any(|index| {
+            self.futures[index].is_some()
+                && self.selector.try_active_weight(index) > Some(W::ZERO)
+                && self.shared.readies[index].load(Ordering::SeqCst)
+        })
+    }
+
+    // 按交替加权轮询的顺序轮询已就绪的子future，直到有子future完成，或没有已就绪的子future
+    fn poll_children(&mut self) -> Poll<Option<(usize, T)>> {
+        //最多轮询子future的数量次，避免总是立即唤醒自己的子future独占当前轮询
+        for _ in 0..LEN {
+            if !self.has_ready() {
+                //没有已就绪的子future，则等待唤醒
+                return Poll::Pending;
+            }
+
+            let futures = &self.futures;
+            let readies = &self.shared.readies;
+            let index = match self.selector.try_select_where(|index| {
+                futures[index].is_some() && readies[index].load(Ordering::SeqCst)
+            }) {
+                None => return Poll::Pending,
+                Some(index) => index,
+            };
+
+            //先清除就绪状态，再轮询子future，子future在轮询中唤醒时会重新就绪
+            self.shared.readies[index].store(false, Ordering::SeqCst);
+            let mut child_cx = Context::from_waker(&self.wakers[index]);
+            if let Some(future) = self.futures[index].as_mut() {
+                if let Poll::Ready(output) = future.as_mut().poll(&mut child_cx) {
+                    self.futures[index] = None;
+                    self.len -= 1;
+                    return Poll::Ready(Some((index, output)));
+                }
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
+impl<T, W: Weight, const LEN: usize> Future for GenericWeightedSelect<T, W, LEN> {
+    type Output = Option<(usize, T)>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if this.len == 0 {
+            //所有子future都已完成
+            return Poll::Ready(None);
+        }
+
+        //先登记唤醒器，再检查就绪状态，避免错过检查后的唤醒，
+        //轮询中子future的唤醒只设置就绪状态，在轮询结束后统一检查
+        this.shared.register(cx.waker());
+        this.shared.polling.store(true, Ordering::SeqCst);
+        let result = this.poll_children();
+        this.shared.polling.store(false, Ordering::SeqCst);
+
+        if result.is_pending() && this.has_ready() {
+            //仍然有已就绪的子future，则立即唤醒，让出当前轮询
+            cx.waker().wake_by_ref();
+        }
+
+        result
+    }
+}
+
+///
+/// 加权选择的future与子future的唤醒器共享的就绪状态
+///
+struct Shared<const LEN: usize> {
+    waker:      Mutex<Option<Waker>>,   //加权选择的future的唤醒器
+    polling:    AtomicBool,             //加权选择的future是否正在轮询
+    readies:    [AtomicBool; LEN],      //每个子future是否已就绪
+}
+
+impl<const LEN: usize> Shared<LEN> {
+    // 登记加权选择的future的唤醒器
+    fn register(&self, waker: &Waker) {
+        let mut current = self.waker.lock().unwrap();
+        match current.as_ref() {
+            Some(current) if current.will_wake(waker) => (),
+            _ => *current = Some(waker.clone()),
+        }
+    }
+}
+
+///
+/// 子future的唤醒器，唤醒时设置子future已就绪，并唤醒加权选择的future
+///
+struct ChildWaker<const LEN: usize> {
+    index:  usize,              //子future的索引
+    shared: Arc<Shared<LEN>>,   //共享的就绪状态
+}
+
+impl<const LEN: usize> Wake for ChildWaker<LEN> {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.shared.readies[self.index].store(true, Ordering::SeqCst);
+        if self.shared.polling.load(Ordering::SeqCst) {
+            //加权选择的future正在轮询，会在轮询结束后检查就绪状态
+            return;
+        }
+
+        //释放锁后再唤醒，避免唤醒时立即轮询加权选择的future导致死锁
+        let waker = self.shared.waker.lock().unwrap().clone();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
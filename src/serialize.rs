@@ -0,0 +1,175 @@
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::{Serialize, Serializer, Deserialize, Deserializer,
+            ser::SerializeTuple,
+            de::{self, SeqAccess, Visitor}};
+
+use crate::{Weight, WrrError, ChangePolicy, GenericIWRRSelector};
+
+///
+/// 交替加权轮询选择器的可序列化状态，不包括可以从权重数组和可用状态推导的状态
+///
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "IWRRSelector")]
+struct State<W: Weight, const LEN: usize> {
+    pos:        usize,          //当前选择的位置
+    round:      W,              //选择的当前轮数
+    max_weight: W,              //最大的权重
+    policy:     ChangePolicy,   //改变权重的生效策略
+    #[serde(default)]
+    normalized: bool,           //是否约简生效的权重
+    #[serde(with = "array")]
+    weights:    [W; LEN],       //待选择的权重数组
+    #[serde(with = "array")]
+    pending:    [W; LEN],       //最新改变的权重数组
+    #[serde(with = "array")]
+    skips:      [W; LEN],       //每个位置在当前周期中需要跳过的次数
+    #[serde(with = "array", default = "all_available")]
+    available:  [bool; LEN],    //每个位置是否可用
+}
+
+// 获取所有位置都可用的可用状态，用于反序列化没有可用状态的旧状态
+fn all_available<const LEN: usize>() -> [bool; LEN] {
+    [true; LEN]
+}
+
+impl<W, const LEN: usize> Serialize for GenericIWRRSelector<W, LEN>
+    where W: Weight + Serialize {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer {
+        State {
+            pos: self.pos,
+            round: self.round,
+            max_weight: self.max_weight,
+            policy: self.policy,
+            normalized: self.normalized,
+            weights: self.weights,
+            pending: self.pending,
+            skips: self.skips,
+            available: self.available,
+        }.serialize(serializer)
+    }
+}
+
+impl<'de, W, const LEN: usize> Deserialize<'de> for GenericIWRRSelector<W, LEN>
+    where W: Weight + Deserialize<'de> {
+    /// 反序列化交替加权轮询选择器，并校验状态，状态无效则返回错误
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de> {
+        let state = State::<W, LEN>::deserialize(deserializer)?;
+        if LEN == 0 {
+            return Err(de::Error::custom(WrrError::EmptySelector));
+        }
+
+        let mut max_weight = W::ZERO;
+        let mut eligible = 0;
+        for index in 0..LEN {
+            for weight in [state.weights[index], state.pending[index]] {
+                if !weight.is_valid() {
+                    return Err(de::Error::custom(WrrError::InvalidWeight {
+                        index,
+                        weight: weight.as_u64(),
+                    }));
+                }
+            }
+
+            let weight = state.weights[index];
+            if weight > W::ZERO {
+                eligible += 1;
+            }
+            if max_weight < weight {
+                max_weight = weight;
+            }
+
+            let skip = state.skips[index];
+            if skip > W::ZERO && skip >= weight {
+                return Err(de::Error::custom(format_args!("invalid state, index: {}, skip: {}, reason: skip exceeds weight",
+                                                          index,
+                                                          skip.as_u64())));
+            }
+        }
+
+        if !state.round.is_valid() {
+            return Err(de::Error::custom(format_args!("invalid state, round: {}, reason: invalid round",
+                                                      state.round.as_u64())));
+        }
+
+        if state.max_weight != max_weight {
+            return Err(de::Error::custom(format_args!("invalid state, max_weight: {}, reason: inconsistent with weights",
+                                                      state.max_weight.as_u64())));
+        }
+
+        if state.pos > LEN {
+            return Err(de::Error::custom(format_args!("invalid state, pos: {}, len: {}, reason: pos out of range",
+                                                      state.pos,
+                                                      LEN)));
+        }
+
+        let changed = state.weights != GenericIWRRSelector::effective(&state.pending, state.normalized);
+        if changed && state.policy == ChangePolicy::Immediate {
+            return Err(de::Error::custom("invalid state, reason: pending weights with immediate policy"));
+        }
+
+        Ok(GenericIWRRSelector {
+            pos: state.pos,
+            round: state.round,
+            max_weight,
+            eligible,
+            policy: state.policy,
+            changed,
+            normalized: state.normalized,
+            skipping: state.skips.iter().any(|skip| *skip > W::ZERO),
+            weights: state.weights,
+            pending: state.pending,
+            skips: state.skips,
+            unavailable: state.available.iter().filter(|available| !**available).count(),
+            available: state.available,
+        })
+    }
+}
+
+// 以定长元组序列化和反序列化任意长度的权重数组或可用状态数组
+mod array {
+    use super::*;
+
+    pub fn serialize<T, S, const LEN: usize>(array: &[T; LEN],
+                                             serializer: S) -> Result<S::Ok, S::Error>
+        where T: Serialize,
+              S: Serializer {
+        let mut tuple = serializer.serialize_tuple(LEN)?;
+        for value in array {
+            tuple.serialize_element(value)?;
+        }
+        tuple.end()
+    }
+
+    pub fn deserialize<'de, T, D, const LEN: usize>(deserializer: D) -> Result<[T; LEN], D::Error>
+        where T: Copy + Default + Deserialize<'de>,
+              D: Deserializer<'de> {
+        deserializer.deserialize_tuple(LEN, ArrayVisitor(PhantomData))
+    }
+
+    struct ArrayVisitor<T, const LEN: usize>(PhantomData<T>);
+
+    impl<'de, T, const LEN: usize> Visitor<'de> for ArrayVisitor<T, LEN>
+        where T: Copy + Default + Deserialize<'de> {
+        type Value = [T; LEN];
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            write!(formatter, "an array of {} values", LEN)
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where A: SeqAccess<'de> {
+            let mut array = [T::default(); LEN];
+            for (index, value) in array.iter_mut().enumerate() {
+                *value = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(index, &self))?;
+            }
+
+            Ok(array)
+        }
+    }
+}
@@ -0,0 +1,134 @@
+///
+/// 平滑加权轮询选择器，使用nginx的当前权重与有效权重算法
+///
+#[derive(Debug, Clone)]
+pub struct SmoothWRRSelector<const LEN: usize> {
+    total_weight:   usize,          //有效权重的总和
+    currents:       [isize; LEN],   //当前权重数组
+    weights:        [u8; LEN],      //待选择的权重数组
+}
+
+impl<const LEN: usize> Default for SmoothWRRSelector<LEN> {
+    /// 默认构建指定长度且权重相同的平滑加权轮询选择器
+    fn default() -> Self {
+        Self::new([1; LEN])
+    }
+}
+
+impl<const LEN: usize> SmoothWRRSelector<LEN> {
+    /// 构建指定待选择的权重数组的平滑加权轮询选择器
+    pub fn new(weights: [u8; LEN]) -> Self {
+        let mut total_weight = 0;
+        for weight in weights {
+            if weight == u8::MAX {
+                panic!("Create SmoothWRRSelector failed, weight: {}, reason: invalid weight",
+                       weight);
+            }
+
+            total_weight += weight as usize;
+        }
+
+        SmoothWRRSelector {
+            total_weight,
+            currents: [0; LEN],
+            weights,
+        }
+    }
+
+    /// 获取待选择的权重数组的长度
+    pub const fn len(&self) -> usize {
+        LEN
+    }
+
+    /// 判断待选择的权重数组是否为空
+    pub const fn is_empty(&self) -> bool {
+        LEN == 0
+    }
+
+    /// 获取权重的总和，即一个选择周期的长度
+    pub fn total_weight(&self) -> usize {
+        self.total_weight
+    }
+
+    /// 尝试获取指定位置的当前权重
+    pub fn try_current_weight(&self, index: usize) -> Option<isize> {
+        if index >= self.len() {
+            None
+        } else {
+            Some(self.currents[index])
+        }
+    }
+
+    /// 尝试获取指定位置的权重
+    pub fn try_weight(&self, index: usize) -> Option<u8> {
+        if index >= self.len() {
+            None
+        } else {
+            Some(self.weights[index])
+        }
+    }
+
+    /// 改变指定位置的权重，改变成功则返回指定位置的上个权重
+    pub fn change_weight(&mut self,
+                         index: usize,
+                         weight: u8) -> Option<u8> {
+        if weight == u8::MAX {
+            return None;
+        }
+
+        if let Some(old) = self.try_weight(index) {
+            self.total_weight = self.total_weight - old as usize + weight as usize;
+            self.weights[index] = weight;
+            self.reset(); //权重改变后，重新开始平滑选择
+            Some(old)
+        } else {
+            None
+        }
+    }
+
+    /// 根据权重选择，并返回被选择的位置，所有权重都为零则panic
+    pub fn select(&mut self) -> usize {
+        if let Some(pos) = self.try_select() {
+            pos
+        } else {
+            panic!("Select failed, len: {}, reason: all weights are zero",
+                   self.len());
+        }
+    }
+
+    /// 尝试根据权重选择，并返回被选择的位置，所有权重都为零则返回空
+    pub fn try_select(&mut self) -> Option<usize> {
+        if self.total_weight == 0 {
+            //没有可以被选择的位置
+            return None;
+        }
+
+        let mut selected: Option<usize> = None;
+        for pos in 0..self.len() {
+            let weight = self.weights[pos];
+            if weight == 0 {
+                //被忽略，则继续下一个位置的选择
+                continue;
+            }
+
+            //增加当前位置的当前权重，并记录当前权重最大的位置
+            self.currents[pos] += weight as isize;
+            match selected {
+                Some(index) if self.currents[index] >= self.currents[pos] => (),
+                _ => selected = Some(pos),
+            }
+        }
+
+        if let Some(pos) = selected {
+            //减少被选择位置的当前权重
+            self.currents[pos] -= self.total_weight as isize;
+        }
+
+        selected
+    }
+
+    /// 重置选择器
+    pub fn reset(&mut self) {
+        self.currents = [0; LEN];
+    }
+}
@@ -0,0 +1,283 @@
+/// 单位权重的步长，可以被1到16的所有权重整除，其它权重的步长的余数会累积到行程值
+const STRIDE1: u64 = 720720;
+
+/// 需要平移行程值的阈值
+const REBASE_PASS: u64 = u64::MAX / 2;
+
+/// 不在堆中的位置
+const NOT_IN_HEAP: usize = usize::MAX;
+
+///
+/// 步长调度选择器，使用最小堆维护每个位置的行程值，选择的时间复杂度为O(logN)
+///
+#[derive(Debug, Clone)]
+pub struct StrideSelector<const LEN: usize> {
+    global_pass:    u64,            //最近一次被选择位置的行程值
+    size:           usize,          //堆的长度
+    heap:           [usize; LEN],   //以行程值排序的最小堆，堆中的值为位置
+    locations:      [usize; LEN],   //每个位置在堆中的下标
+    passes:         [u64; LEN],     //待选择的行程值数组
+    remainders:     [u8; LEN],      //每个位置累积的步长余数，达到权重时行程值加一
+    weights:        [u8; LEN],      //待选择的权重数组
+}
+
+impl<const LEN: usize> Default for StrideSelector<LEN> {
+    /// 默认构建指定长度且权重相同的步长调度选择器
+    fn default() -> Self {
+        Self::new([1; LEN])
+    }
+}
+
+impl<const LEN: usize> StrideSelector<LEN> {
+    /// 构建指定待选择的权重数组的步长调度选择器
+    pub fn new(weights: [u8; LEN]) -> Self {
+        for weight in weights {
+            if weight == u8::MAX {
+                panic!("Create StrideSelector failed, weight: {}, reason: invalid weight",
+                       weight);
+            }
+        }
+
+        let mut selector = StrideSelector {
+            global_pass: 0,
+            size: 0,
+            heap: [0; LEN],
+            locations: [NOT_IN_HEAP; LEN],
+            passes: [0; LEN],
+            remainders: [0; LEN],
+            weights,
+        };
+        selector.reset();
+
+        selector
+    }
+
+    /// 获取待选择的权重数组的长度
+    pub const fn len(&self) -> usize {
+        LEN
+    }
+
+    /// 判断待选择的权重数组是否为空
+    pub const fn is_empty(&self) -> bool {
+        LEN == 0
+    }
+
+    /// 获取最近一次被选择位置的行程值
+    pub fn global_pass(&self) -> u64 {
+        self.global_pass
+    }
+
+    /// 尝试获取指定位置的行程值
+    pub fn try_pass(&self, index: usize) -> Option<u64> {
+        if index >= self.len() {
+            None
+        } else {
+            Some(self.passes[index])
+        }
+    }
+
+    /// 尝试获取指定位置的权重
+    pub fn try_weight(&self, index: usize) -> Option<u8> {
+        if index >= self.len() {
+            None
+        } else {
+            Some(self.weights[index])
+        }
+    }
+
+    /// 改变指定位置的权重，改变成功则返回指定位置的上个权重，
+    /// 指定位置剩余的行程会按新旧步长的比例缩放，以保持步长调度的公平性
+    pub fn change_weight(&mut self,
+                         index: usize,
+                         weight: u8) -> Option<u8> {
+        if weight == u8::MAX {
+            return None;
+        }
+
+        let old = self.try_weight(index)?;
+        self.weights[index] = weight;
+        self.remainders[index] = 0;
+
+        if weight == 0 {
+            //被禁用的位置，则从堆中移除
+            if self.locations[index] != NOT_IN_HEAP {
+                self.remove(index);
+            }
+        } else if old == 0 {
+            //被启用的位置，则从最近一次被选择位置的行程值开始，并加入堆
+            self.passes[index] = self.global_pass;
+            self.advance(index);
+            self.push(index);
+        } else {
+            //按新旧步长的比例缩放剩余的行程，并调整在堆中的位置
+            let remaining = self.passes[index].saturating_sub(self.global_pass) as u128;
+            let remaining = remaining * stride(weight) as u128 / stride(old) as u128;
+            self.passes[index] = self.global_pass + remaining as u64;
+
+            let location = self.locations[index];
+            self.sift_up(location);
+            self.sift_down(self.locations[index]);
+        }
+
+        Some(old)
+    }
+
+    /// 根据权重选择，并返回被选择的位置，所有权重都为零则panic
+    pub fn select(&mut self) -> usize {
+        if let Some(pos) = self.try_select() {
+            pos
+        } else {
+            panic!("Select failed, len: {}, reason: all weights are zero",
+                   self.len());
+        }
+    }
+
+    /// 尝试根据权重选择，并返回被选择的位置，所有权重都为零则返回空
+    pub fn try_select(&mut self) -> Option<usize> {
+        if self.size == 0 {
+            //没有可以被选择的位置
+            return None;
+        }
+
+        //选择行程值最小的位置，并增加被选择位置的行程值
+        let pos = self.heap[0];
+        self.global_pass = self.passes[pos];
+        self.advance(pos);
+        self.sift_down(0);
+
+        if self.global_pass >= REBASE_PASS {
+            //行程值过大，则平移所有位置的行程值
+            self.rebase();
+        }
+
+        Some(pos)
+    }
+
+    /// 重置选择器
+    pub fn reset(&mut self) {
+        self.global_pass = 0;
+        self.size = 0;
+        for index in 0..self.len() {
+            self.locations[index] = NOT_IN_HEAP;
+            self.remainders[index] = 0;
+
+            let weight = self.weights[index];
+            if weight > 0 {
+                self.passes[index] = 0;
+                self.advance(index);
+                self.heap[self.size] = index;
+                self.locations[index] = self.size;
+                self.size += 1;
+            } else {
+                self.passes[index] = 0;
+            }
+        }
+
+        //自底向上建堆
+        for location in (0..self.size / 2).rev() {
+            self.sift_down(location);
+        }
+    }
+
+    // 指定位置的行程值增加一个步长，并累积步长的余数，保证多次选择后的行程值没有累积误差
+    fn advance(&mut self, index: usize) {
+        let weight = self.weights[index];
+        self.passes[index] += stride(weight);
+
+        let remainder = self.remainders[index] as u64 + STRIDE1 % weight as u64;
+        if remainder >= weight as u64 {
+            //余数已满一个行程值
+            self.passes[index] += 1;
+            self.remainders[index] = (remainder - weight as u64) as u8;
+        } else {
+            self.remainders[index] = remainder as u8;
+        }
+    }
+
+    // 所有位置的行程值减去最近一次被选择位置的行程值
+    fn rebase(&mut self) {
+        let base = self.global_pass;
+        for pass in self.passes.iter_mut() {
+            *pass = pass.saturating_sub(base);
+        }
+        self.global_pass = 0;
+    }
+
+    // 将指定位置加入堆
+    fn push(&mut self, index: usize) {
+        let location = self.size;
+        self.heap[location] = index;
+        self.locations[index] = location;
+        self.size += 1;
+        self.sift_up(location);
+    }
+
+    // 将指定位置从堆中移除
+    fn remove(&mut self, index: usize) {
+        let location = self.locations[index];
+        self.size -= 1;
+        self.swap(location, self.size);
+        self.locations[index] = NOT_IN_HEAP;
+
+        if location < self.size {
+            self.sift_up(location);
+            self.sift_down(self.locations[self.heap[location]]);
+        }
+    }
+
+    // 比较堆中指定下标的两个位置，行程值相同时位置小的优先
+    fn less(&self, x: usize, y: usize) -> bool {
+        let x = self.heap[x];
+        let y = self.heap[y];
+        (self.passes[x], x) < (self.passes[y], y)
+    }
+
+    // 交换堆中指定下标的两个位置
+    fn swap(&mut self, x: usize, y: usize) {
+        self.heap.swap(x, y);
+        self.locations[self.heap[x]] = x;
+        self.locations[self.heap[y]] = y;
+    }
+
+    // 上浮堆中指定下标的位置
+    fn sift_up(&mut self, mut location: usize) {
+        while location > 0 {
+            let parent = (location - 1) / 2;
+            if !self.less(location, parent) {
+                break;
+            }
+
+            self.swap(location, parent);
+            location = parent;
+        }
+    }
+
+    // 下沉堆中指定下标的位置
+    fn sift_down(&mut self, mut location: usize) {
+        loop {
+            let left = location * 2 + 1;
+            if left >= self.size {
+                break;
+            }
+
+            let right = left + 1;
+            let child = if right < self.size && self.less(right, left) {
+                right
+            } else {
+                left
+            };
+            if !self.less(child, location) {
+                break;
+            }
+
+            self.swap(location, child);
+            location = child;
+        }
+    }
+}
+
+// 获取指定权重的步长
+#[inline]
+const fn stride(weight: u8) -> u64 {
+    STRIDE1 / weight as u64
+}
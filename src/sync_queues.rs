@@ -0,0 +1,280 @@
+use std::ptr;
+use std::fmt;
+use std::vec::Vec;
+use std::boxed::Box;
+use std::cell::UnsafeCell;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+
+use crate::{Weight, WrrError, GenericIWRRSelector};
+
+///
+/// 线程安全的加权多队列，权重类型为u8
+///
+pub type SyncWeightedQueues<T, const LEN: usize> = GenericSyncWeightedQueues<T, u8, LEN>;
+
+///
+/// 指定权重类型的线程安全的加权多队列，多个生产者线程可以无锁的并发增加成员到每个类别的队列，
+/// 消费者按交替加权轮询从不为空的队列中弹出成员，弹出时持有选择器的锁，所以同时只有一个消费者弹出
+///
+pub struct GenericSyncWeightedQueues<T, W: Weight, const LEN: usize> {
+    selector:   Mutex<GenericIWRRSelector<W, LEN>>, //类别的选择器，同时也是消费者的锁
+    len:        AtomicUsize,                        //所有队列的成员数量
+    sleepers:   AtomicUsize,                        //等待成员的消费者数量
+    waiters:    Mutex<Vec<Thread>>,                 //等待成员的消费者线程
+    lens:       [AtomicUsize; LEN],                 //以类别索引的队列的成员数量
+    queues:     [Queue<T>; LEN],                    //以类别索引的队列数组
+}
+
+impl<T, W: Weight, const LEN: usize> fmt::Debug for GenericSyncWeightedQueues<T, W, LEN> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SyncWeightedQueues")
+            .field("len", &self.len())
+            .field("lens", &self.lens)
+            .field("selector", &self.selector)
+            .finish()
+    }
+}
+
+impl<T, W: Weight, const LEN: usize> Default for GenericSyncWeightedQueues<T, W, LEN> {
+    /// 默认构建指定类别数量且权重相同的线程安全的加权多队列
+    fn default() -> Self {
+        Self::new([W::ONE; LEN])
+    }
+}
+
+impl<T, W: Weight, const LEN: usize> GenericSyncWeightedQueues<T, W, LEN> {
+    /// 构建指定类别权重数组的线程安全的加权多队列，权重数组无效则立即panic
+    pub fn new(weights: [W; LEN]) -> Self {
+        match Self::try_new(weights) {
+            Err(e) => panic!("Create SyncWeightedQueues failed, reason: {}", e),
+            Ok(queues) => queues,
+        }
+    }
+
+    /// 尝试构建指定类别权重数组的线程安全的加权多队列，
+    /// 权重数组为空、包含无效的权重或所有权重都为零则返回错误
+    pub fn try_new(weights: [W; LEN]) -> Result<Self, WrrError> {
+        let selector = GenericIWRRSelector::try_new(weights)?;
+
+        Ok(GenericSyncWeightedQueues {
+            selector: Mutex::new(selector),
+            len: AtomicUsize::new(0),
+            sleepers: AtomicUsize::new(0),
+            waiters: Mutex::new(Vec::new()),
+            lens: core::array::from_fn(|_| AtomicUsize::new(0)),
+            queues: core::array::from_fn(|_| Queue::new()),
+        })
+    }
+
+    /// 获取类别的数量
+    pub const fn classes(&self) -> usize {
+        LEN
+    }
+
+    /// 获取所有队列的成员数量，并发增加或弹出时只是近似值
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+
+    /// 判断所有队列是否都为空，并发增加或弹出时只是近似值
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 获取指定类别的队列的成员数量，类别不存在则返回0，并发增加或弹出时只是近似值
+    pub fn len_of(&self, class: usize) -> usize {
+        self.lens
+            .get(class)
+            .map_or(0, |len| len.load(Ordering::Acquire))
+    }
+
+    /// 尝试获取指定类别的权重
+    pub fn try_weight(&self, class: usize) -> Option<W> {
+        self.selector.lock().unwrap().try_weight(class)
+    }
+
+    /// 改变指定类别的权重，改变成功则返回指定类别的上个权重
+    pub fn change_weight(&self,
+                         class: usize,
+                         weight: W) -> Option<W> {
+        self.selector.lock().unwrap().change_weight(class, weight)
+    }
+
+    /// 在指定类别的队列尾部增加成员，并唤醒等待成员的消费者，类别不存在则立即panic
+    pub fn push(&self, class: usize, item: T) {
+        if let Err(e) = self.try_push(class, item) {
+            panic!("Push item failed, reason: {}", e);
+        }
+    }
+
+    /// 尝试在指定类别的队列尾部增加成员，并唤醒等待成员的消费者，类别不存在则返回错误
+    pub fn try_push(&self, class: usize, item: T) -> Result<(), WrrError> {
+        let queue = self.queues.get(class).ok_or(WrrError::IndexOutOfRange {
+            index: class,
+            len: LEN,
+        })?;
+
+        //成员已链接到队列后再增加数量，消费者只弹出已计数的成员，所以数量不会小于零，
+        //先增加所有队列的成员数量，再增加类别的队列的成员数量，保证弹出成员时两个数量都已增加
+        queue.push(item);
+        self.len.fetch_add(1, Ordering::SeqCst);
+        self.lens[class].fetch_add(1, Ordering::SeqCst);
+
+        if self.sleepers.load(Ordering::SeqCst) > 0 {
+            //有等待成员的消费者，则唤醒
+            for waiter in self.waiters.lock().unwrap().iter() {
+                waiter.unpark();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 根据权重从不为空的队列的头部弹出成员，并返回成员的类别和成员，
+    /// 空队列的类别会被跳过，不为空的队列之间仍然保持权重的比例，
+    /// 所有队列都为空或不为空的队列的权重都为零则返回空
+    pub fn pop(&self) -> Option<(usize, T)> {
+        if self.len.load(Ordering::SeqCst) == 0 {
+            //所有队列都为空
+            return None;
+        }
+
+        let mut selector = self.selector.lock().unwrap();
+        let lens = &self.lens;
+        let queues = &self.queues;
+        //持有选择器的锁，所以只有当前线程访问队列的消费端，且只弹出已计数的成员
+        let class = selector.try_select_where(|class| {
+            lens[class].load(Ordering::SeqCst) > 0 && unsafe { queues[class].is_ready() }
+        })?;
+        let item = unsafe { queues[class].pop()? };
+        self.lens[class].fetch_sub(1, Ordering::Release);
+        self.len.fetch_sub(1, Ordering::Release);
+
+        Some((class, item))
+    }
+
+    /// 根据权重从不为空的队列的头部弹出成员，没有可以弹出的成员则阻塞当前线程，
+    /// 直到有成员增加后被唤醒，超时则返回空
+    pub fn pop_wait(&self, timeout: Duration) -> Option<(usize, T)> {
+        //超时时间无法表示，则一直等待
+        let deadline = Instant::now().checked_add(timeout);
+        let current = thread::current();
+        loop {
+            if let Some(item) = self.pop() {
+                return Some(item);
+            }
+
+            //登记等待的线程后再次尝试弹出，避免错过登记前增加的成员
+            self.waiters.lock().unwrap().push(current.clone());
+            self.sleepers.fetch_add(1, Ordering::SeqCst);
+            let item = self.pop();
+
+            let mut timeout = false;
+            if item.is_none() {
+                match deadline {
+                    None => thread::park(),
+                    Some(deadline) => {
+                        let now = Instant::now();
+                        if now < deadline {
+                            thread::park_timeout(deadline - now);
+                        } else {
+                            timeout = true;
+                        }
+                    },
+                }
+            }
+
+            self.sleepers.fetch_sub(1, Ordering::SeqCst);
+            self.waiters
+                .lock()
+                .unwrap()
+                .retain(|waiter| waiter.id() != current.id());
+
+            if item.is_some() || timeout {
+                return item;
+            }
+        }
+    }
+}
+
+///
+/// 多生产者单消费者的无锁队列的节点
+///
+struct Node<T> {
+    next:   AtomicPtr<Node<T>>, //下一个节点
+    value:  Option<T>,          //节点的成员，哨兵节点的成员为空
+}
+
+///
+/// 多生产者单消费者的无锁队列，生产者替换头节点，消费者从哨兵节点开始弹出
+///
+struct Queue<T> {
+    head:   AtomicPtr<Node<T>>,         //最近增加的节点
+    tail:   UnsafeCell<*mut Node<T>>,   //哨兵节点，只由消费者访问
+}
+
+unsafe impl<T: Send> Send for Queue<T> {}
+unsafe impl<T: Send> Sync for Queue<T> {}
+
+impl<T> Drop for Queue<T> {
+    fn drop(&mut self) {
+        //已独占队列，所有节点都已链接
+        let mut node = *self.tail.get_mut();
+        while !node.is_null() {
+            let next = unsafe { (*node).next.load(Ordering::Relaxed) };
+            drop(unsafe { Box::from_raw(node) });
+            node = next;
+        }
+    }
+}
+
+impl<T> Queue<T> {
+    // 构建空的队列
+    fn new() -> Self {
+        let stub = Box::into_raw(Box::new(Node {
+            next: AtomicPtr::new(ptr::null_mut()),
+            value: None,
+        }));
+
+        Queue {
+            head: AtomicPtr::new(stub),
+            tail: UnsafeCell::new(stub),
+        }
+    }
+
+    // 在队列尾部增加成员，可以被多个生产者并发调用
+    fn push(&self, value: T) {
+        let node = Box::into_raw(Box::new(Node {
+            next: AtomicPtr::new(ptr::null_mut()),
+            value: Some(value),
+        }));
+
+        let prev = self.head.swap(node, Ordering::AcqRel);
+        unsafe {
+            (*prev).next.store(node, Ordering::Release);
+        }
+    }
+
+    // 判断队列头部是否有已链接的成员，同时只能被一个消费者调用
+    unsafe fn is_ready(&self) -> bool {
+        let tail = *self.tail.get();
+        !(*tail).next.load(Ordering::Acquire).is_null()
+    }
+
+    // 从队列头部弹出已链接的成员，同时只能被一个消费者调用
+    unsafe fn pop(&self) -> Option<T> {
+        let tail = *self.tail.get();
+        let next = (*tail).next.load(Ordering::Acquire);
+        if next.is_null() {
+            return None;
+        }
+
+        //弹出的节点成为新的哨兵节点
+        *self.tail.get() = next;
+        drop(Box::from_raw(tail));
+        (*next).value.take()
+    }
+}
@@ -0,0 +1,59 @@
+use core::fmt::{Debug, Display};
+use core::ops::{Add, Sub};
+
+///
+/// 权重，由u8、u16、u32、u64和usize实现
+///
+pub trait Weight: Copy
+    + Ord
+    + Default
+    + Debug
+    + Display
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + 'static {
+    /// 零权重，权重为零的位置不会被选择
+    const ZERO: Self;
+
+    /// 单位权重
+    const ONE: Self;
+
+    /// 权重的上限，有效的权重必须小于权重的上限
+    const MAX: Self;
+
+    /// 判断是否是有效的权重
+    #[inline]
+    fn is_valid(self) -> bool {
+        self < Self::MAX
+    }
+
+    /// 转换为u64
+    fn as_u64(self) -> u64;
+
+    /// 从u64转换，超出范围则截断
+    fn from_u64(value: u64) -> Self;
+}
+
+macro_rules! impl_weight {
+    ($($ty:ty),*) => {
+        $(
+            impl Weight for $ty {
+                const ZERO: Self = 0;
+                const ONE: Self = 1;
+                const MAX: Self = <$ty>::MAX;
+
+                #[inline]
+                fn as_u64(self) -> u64 {
+                    self as u64
+                }
+
+                #[inline]
+                fn from_u64(value: u64) -> Self {
+                    value as $ty
+                }
+            }
+        )*
+    };
+}
+
+impl_weight!(u8, u16, u32, u64, usize);
@@ -1,7 +1,32 @@
-use std::cell::{RefCell, UnsafeCell};
-use std::marker::PhantomData;
+use std::thread;
+use std::sync::{Arc, Mutex};
+use std::pin::Pin;
+use std::future::{self, Future};
+use std::task::{Context, Poll, Waker, RawWaker, RawWakerVTable};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, RecvError, TryRecvError, RecvTimeoutError};
+use std::time::{Duration, Instant};
 
-use wrr::IWRRSelector;
+use pi_wrr::{Selector,
+             Weight,
+             WrrError,
+             ChangePolicy,
+             GenericIWRRSelector,
+             IWRRSelector,
+             IWRRSelectorByWider,
+             SmoothWRRSelector,
+             DeficitRRSelector,
+             StrideSelector,
+             AliasSelector,
+             DynIWRRSelector,
+             GenericDynIWRRSelector,
+             WeightedPool,
+             AtomicIWRRSelector,
+             WeightedQueues,
+             GenericWeightedQueues,
+             SyncWeightedQueues,
+             WeightedReceiver,
+             WeightedSelect};
 
 #[test]
 fn test() {
@@ -106,6 +131,1567 @@ fn test() {
 }
 
 #[test]
+fn test_cycle_shares() {
+    const CYCLES: usize = 10;
+
+    assert_cycle_shares([1, 0], CYCLES);
+    assert_cycle_shares([1, 1], CYCLES);
+    assert_cycle_shares([2, 1], CYCLES);
+    assert_cycle_shares([254, 1], CYCLES);
+    assert_cycle_shares([6, 3, 1], CYCLES);
+    assert_cycle_shares([18, 16, 12, 14, 8, 10, 4, 6, 2, 1], CYCLES);
+}
+
+fn assert_cycle_shares<const LEN: usize>(weights: [u8; LEN], cycles: usize) {
+    assert_cycle_counts(&mut IWRRSelector::new(weights), weights, cycles);
+    assert_cycle_counts(&mut SmoothWRRSelector::new(weights), weights, cycles);
+    assert_cycle_counts(&mut DeficitRRSelector::new(weights), weights, cycles);
+}
+
+// 从当前状态开始选择多个周期，并检查每个周期中每个位置的选择次数与权重相同，当前状态必须在周期的开始
+fn assert_cycle_counts<S, W, const LEN: usize>(selector: &mut S, weights: [W; LEN], cycles: usize)
+    where S: Selector<Weight = W>,
+          W: Weight {
+    let cycle_len: usize = weights.iter().map(|weight| weight.as_u64() as usize).sum();
+
+    for _ in 0..cycles {
+        let mut counts = [0; LEN];
+        for _ in 0..cycle_len {
+            counts[selector.select()] += 1;
+        }
+        for (count, weight) in counts.iter().zip(weights.iter()) {
+            assert_eq!(*count as u64, weight.as_u64());
+        }
+    }
+}
+
+#[test]
+fn test_smooth() {
+    let mut selector = SmoothWRRSelector::new([5, 1, 1]);
+    let mut picks = Vec::new();
+    for _ in 0..7 {
+        picks.push(selector.select());
+    }
+    assert_eq!(picks, vec![0, 0, 1, 0, 2, 0, 0]);
+
+    assert_eq!(selector.change_weight(1, 3), Some(1));
+    assert_eq!(selector.total_weight(), 9);
+    assert_eq!(selector.change_weight(3, 1), None);
+    assert_eq!(selector.change_weight(0, u8::MAX), None);
+    assert_eq!(selector.try_weight(1), Some(3));
+}
+
+#[test]
+fn test_deficit() {
+    const COUNT: usize = 100000;
+    const COSTS: [usize; 3] = [1500, 64, 600];
+
+    //按开销公平分配，而不是按选择的次数
+    let mut selector = DeficitRRSelector::with_quantum([2, 1, 1], 500);
+    let mut picks = [0; 3];
+    let mut served = [0; 3];
+    for _ in 0..COUNT {
+        let index = selector.select_with_cost(|index| COSTS[index]);
+        picks[index] += 1;
+        served[index] += COSTS[index];
+    }
+    let bound = COSTS.iter().max().unwrap() + 2 * selector.quantum();
+    assert!(served[0].abs_diff(served[1] * 2) <= bound * 2);
+    assert!(served[1].abs_diff(served[2]) <= bound);
+    assert!(picks[1] > picks[0]);
+    println!("total: {}, picks: {:?}, served: {:?}", COUNT, picks, served);
+
+    assert_eq!(selector.change_weight(0, 0), Some(2));
+    assert_eq!(selector.try_deficit(0), Some(0));
+    for _ in 0..COUNT {
+        assert_ne!(selector.select_with_cost(|index| COSTS[index]), 0);
+    }
+    assert_eq!(selector.change_weight(3, 1), None);
+    assert_eq!(selector.change_weight(1, u8::MAX), None);
+}
+
+#[test]
+fn test_stride() {
+    const CYCLES: usize = 10;
+
+    //步长可以被整除时，每个周期的选择次数与权重完全相同
+    for weights in [[1, 0, 0], [1, 1, 0], [2, 1, 0], [6, 3, 1]] {
+        assert_cycle_counts(&mut StrideSelector::new(weights), weights, CYCLES);
+    }
+    let weights = [18, 16, 12, 14, 8, 10, 4, 6, 2, 1];
+    assert_cycle_counts(&mut StrideSelector::new(weights), weights, CYCLES);
+
+    //步长不能被整除时，选择次数的误差不会随周期累积
+    for weights in [[254, 1], [17, 16]] {
+        let cycle_len: usize = weights.iter().map(|weight| *weight as usize).sum();
+        let mut selector = StrideSelector::new(weights);
+        let mut counts = [0usize; 2];
+        for cycle in 1..=20000 {
+            for _ in 0..cycle_len {
+                counts[selector.select()] += 1;
+            }
+            for (count, weight) in counts.iter().zip(weights.iter()) {
+                assert!(count.abs_diff(*weight as usize * cycle) <= 1,
+                        "weights: {:?}, cycle: {}, counts: {:?}", weights, cycle, counts);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_stride_change_weight() {
+    const COUNT: usize = 60000;
+
+    let mut selector = StrideSelector::new([1, 1, 1]);
+    for _ in 0..COUNT {
+        selector.select();
+    }
+
+    //改变权重后，选择次数收敛到新的权重
+    assert_eq!(selector.change_weight(0, 4), Some(1));
+    assert_eq!(selector.change_weight(2, 0), Some(1));
+    let mut counts = [0usize; 3];
+    for _ in 0..COUNT {
+        counts[selector.select()] += 1;
+    }
+    assert_eq!(counts[2], 0);
+    assert!(counts[0].abs_diff(counts[1] * 4) <= 4);
+
+    //重新启用的位置不会因为之前被禁用而获得补偿
+    assert_eq!(selector.change_weight(2, 1), Some(0));
+    let mut counts = [0usize; 3];
+    for _ in 0..COUNT {
+        counts[selector.select()] += 1;
+    }
+    assert!(counts[0].abs_diff(counts[2] * 4) <= 4);
+    assert!(counts[1].abs_diff(counts[2]) <= 1);
+
+    assert_eq!(selector.change_weight(3, 1), None);
+    assert_eq!(selector.change_weight(0, u8::MAX), None);
+}
+
+#[test]
+fn test_stride_large() {
+    const LEN: usize = 4096;
+
+    let mut weights = [0; LEN];
+    for (index, weight) in weights.iter_mut().enumerate() {
+        *weight = (index % 16 + 1) as u8;
+    }
+    assert_cycle_counts(&mut StrideSelector::new(weights), weights, 1);
+}
+
+#[test]
+fn test_alias() {
+    const COUNT: usize = 1000000;
+
+    let weights = [18, 16, 12, 14, 8, 10, 4, 6, 2, 1, 0];
+    let total: usize = weights.iter().map(|weight| *weight as usize).sum();
+    let mut selector = AliasSelector::with_seed(weights, 0x5eed);
+    let mut counts = [0usize; 11];
+    for _ in 0..COUNT {
+        counts[selector.select()] += 1;
+    }
+    assert_eq!(counts[10], 0);
+    for (count, weight) in counts.iter().zip(weights.iter()) {
+        let expect = COUNT * *weight as usize / total;
+        assert!(count.abs_diff(expect) <= COUNT / 200, "count: {}, expect: {}", count, expect);
+    }
+    println!("total: {}, counts: {:?}", COUNT, counts);
+
+    //相同的随机数种子生成相同的选择序列
+    let mut x = AliasSelector::with_seed([3, 2, 1], 42);
+    let mut y = AliasSelector::with_seed([3, 2, 1], 42);
+    let picks: Vec<usize> = (0..100).map(|_| x.select()).collect();
+    assert_eq!(picks, (0..100).map(|_| y.select()).collect::<Vec<usize>>());
+    x.reset();
+    assert_eq!(picks, (0..100).map(|_| x.select()).collect::<Vec<usize>>());
+
+    //改变权重后重建别名表
+    assert_eq!(x.change_weight(0, 0), Some(3));
+    assert_eq!(x.change_weight(1, 0), Some(2));
+    for _ in 0..COUNT / 10 {
+        assert_eq!(x.select(), 2);
+    }
+    assert_eq!(x.change_weight(3, 1), None);
+    assert_eq!(x.change_weight(2, u8::MAX), None);
+
+    //使用调用者提供的随机数生成器
+    let mut selector = AliasSelector::new([1, 1]);
+    assert_eq!(selector.select_with(|| 0), 0);
+    assert_eq!(selector.select_with(|| u64::MAX), 1);
+}
+
+#[test]
+fn test_selector() {
+    const COUNT: usize = 10000;
+
+    assert_selector(IWRRSelector::new([6, 3, 1]), COUNT);
+    assert_selector(IWRRSelectorByWider::new([6, 3, 1]), COUNT);
+    assert_selector(SmoothWRRSelector::new([6, 3, 1]), COUNT);
+    assert_selector(DeficitRRSelector::new([6, 3, 1]), COUNT);
+    assert_selector(StrideSelector::new([6, 3, 1]), COUNT);
+    assert_selector(AliasSelector::with_seed([6, 3, 1], 0x5eed), COUNT);
+}
+
+// 与选择算法无关的检查，改变权重后被禁用的位置不会被选择
+fn assert_selector<S: Selector>(mut selector: S, count: usize)
+    where S::Weight: From<u8> + PartialEq + std::fmt::Debug {
+    assert_eq!(selector.len(), 3);
+    assert!(!selector.is_empty());
+    assert_eq!(selector.try_weight(0), Some(S::Weight::from(6)));
+    assert_eq!(selector.try_weight(3), None);
+
+    let mut counts = [0usize; 3];
+    for _ in 0..count {
+        counts[selector.select()] += 1;
+    }
+    assert!(counts[0] > counts[1] && counts[1] > counts[2]);
+
+    assert_eq!(selector.change_weight(0, S::Weight::from(0)), Some(S::Weight::from(6)));
+    selector.reset();
+    for _ in 0..count {
+        assert_ne!(selector.select(), 0);
+    }
+}
+
+#[test]
+fn test_generic_weight() {
+    const CYCLES: usize = 10;
+
+    assert_cycle_counts(&mut GenericIWRRSelector::new([6u16, 3, 1]), [6, 3, 1], CYCLES);
+    assert_cycle_counts(&mut GenericIWRRSelector::new([6u32, 3, 1]), [6, 3, 1], CYCLES);
+    assert_cycle_counts(&mut GenericIWRRSelector::new([6u64, 3, 1]), [6, 3, 1], CYCLES);
+    assert_cycle_counts(&mut GenericIWRRSelector::new([6usize, 3, 1]), [6, 3, 1], CYCLES);
+    assert_cycle_counts(&mut GenericIWRRSelector::new([1000u16, 1]), [1000, 1], CYCLES);
+
+    //类型别名与指定权重类型的选择器相同
+    let mut x: GenericIWRRSelector<u8, 2> = IWRRSelector::new([2, 1]);
+    let mut y: GenericIWRRSelector<usize, 2> = IWRRSelectorByWider::new([2, 1]);
+    for _ in 0..100 {
+        assert_eq!(x.select(), y.select());
+    }
+
+    //每种权重类型的上限都是无效的权重
+    assert!(!u16::MAX.is_valid());
+    assert!((u32::MAX - 1).is_valid());
+    let mut selector = GenericIWRRSelector::<u32, 2>::default();
+    assert_eq!(selector.change_weight(0, u32::MAX), None);
+    assert_eq!(selector.change_weight(0, u32::MAX - 1), Some(1));
+}
+
+#[test]
+#[should_panic]
+fn test_generic_invalid_weight() {
+    GenericIWRRSelector::new([1u64, u64::MAX]);
+}
+
+#[test]
+fn test_error() {
+    assert_eq!(IWRRSelector::try_new([1, u8::MAX]).unwrap_err(),
+               WrrError::InvalidWeight { index: 1, weight: 255 });
+    assert_eq!(IWRRSelector::try_new([0, 0]).unwrap_err(),
+               WrrError::AllWeightsZero);
+    assert_eq!(IWRRSelector::try_new([]).unwrap_err(),
+               WrrError::EmptySelector);
+    assert_eq!(IWRRSelectorByWider::try_new([3, usize::MAX, 1]).unwrap_err().to_string(),
+               format!("invalid weight, index: 1, weight: {}", usize::MAX));
+
+    let mut selector = IWRRSelector::try_new([2, 1]).unwrap();
+    assert_eq!(selector.try_change_weight(2, 1),
+               Err(WrrError::IndexOutOfRange { index: 2, len: 2 }));
+    assert_eq!(selector.try_change_weight(0, u8::MAX),
+               Err(WrrError::InvalidWeight { index: 0, weight: 255 }));
+    assert_eq!(selector.try_change_weight(0, 3), Ok(2));
+    assert_eq!(selector.change_weight(2, 1), None);
+    assert_eq!(selector.change_weight(0, u8::MAX), None);
+    assert_eq!(selector.try_weight(0), Some(3));
+}
+
+#[test]
+#[should_panic(expected = "all weights are zero")]
+fn test_new_all_weights_zero() {
+    IWRRSelector::new([0, 0]);
+}
+
+#[test]
+fn test_try_select() {
+    //所有权重都被改变为零后，不会无限循环
+    let mut selector = IWRRSelector::new([2, 1]);
+    assert_eq!(selector.eligible_len(), 2);
+    assert_eq!(selector.try_select(), Some(0));
+    selector.change_weight(0, 0);
+    selector.change_weight(1, 0);
+    assert_eq!(selector.eligible_len(), 0);
+    assert_eq!(selector.try_select(), None);
+    selector.change_weight(1, 3);
+    assert_eq!(selector.eligible_len(), 1);
+    assert_eq!(selector.try_select(), Some(1));
+
+    assert_all_zero(IWRRSelectorByWider::new([1, 1]));
+    assert_all_zero(SmoothWRRSelector::new([1, 1]));
+    assert_all_zero(DeficitRRSelector::new([1, 1]));
+    assert_all_zero(StrideSelector::new([1, 1]));
+    assert_all_zero(AliasSelector::with_seed([1, 1], 0x5eed));
+
+    //空的选择器不会无限循环
+    assert_eq!(SmoothWRRSelector::<0>::default().try_select(), None);
+    assert_eq!(DeficitRRSelector::<0>::default().try_select(), None);
+    assert_eq!(StrideSelector::<0>::default().try_select(), None);
+    assert_eq!(AliasSelector::<0>::default().try_select(), None);
+    assert_eq!(IWRRSelector::<0>::default().try_select(), None);
+    assert_eq!(IWRRSelectorByWider::<0>::default().try_select(), None);
+    assert_eq!(AtomicIWRRSelector::<0>::default().try_select(), None);
+
+    //空的选择器可以正常使用，但不能直接构建
+    let mut selector = IWRRSelector::<0>::default();
+    assert!(selector.is_empty());
+    assert_eq!(selector.eligible_len(), 0);
+    assert_eq!(selector.cycle_len(), 0);
+    assert_eq!(selector.try_peek(), None);
+    assert_eq!(selector.upcoming().next(), None);
+    assert_eq!(selector.try_select_run(3), None);
+    assert_eq!(selector.change_weight(0, 1), None);
+    assert_eq!(selector.select_counts(5), [0usize; 0]);
+    selector.seek(3);
+    selector.reset();
+    assert_eq!(selector.try_select(), None);
+    assert_eq!(IWRRSelector::<0>::try_new([]).unwrap_err(), WrrError::EmptySelector);
+}
+
+fn assert_all_zero<S: Selector>(mut selector: S)
+    where S::Weight: From<u8> {
+    assert!(selector.try_select().is_some());
+    selector.change_weight(0, S::Weight::from(0));
+    selector.change_weight(1, S::Weight::from(0));
+    assert_eq!(selector.try_select(), None);
+    selector.reset();
+    assert_eq!(selector.try_select(), None);
+    selector.change_weight(1, S::Weight::from(1));
+    assert_eq!(selector.try_select(), Some(1));
+}
+
+#[test]
+#[should_panic(expected = "all weights are zero")]
+fn test_select_all_weights_zero() {
+    let mut selector = IWRRSelector::new([1]);
+    selector.change_weight(0, 0);
+    selector.select();
+}
+
+#[test]
+fn test_change_weight_max_weight() {
+    let mut selector = IWRRSelector::new([2, 1, 1]);
+    assert_eq!(selector.max_weight(), 2);
+
+    //提高最大的权重
+    selector.change_weight(1, 5);
+    assert_eq!(selector.max_weight(), 5);
+    assert_cycle_counts(&mut selector, [2, 5, 1], 3);
+
+    //降低最大的权重
+    selector.change_weight(1, 1);
+    assert_eq!(selector.max_weight(), 2);
+    selector.change_weight(0, 0);
+    assert_eq!(selector.max_weight(), 1);
+    assert_cycle_counts(&mut selector, [0, 1, 1], 3);
+}
+
+#[test]
+fn test_change_policy() {
+    //改变权重前的选择次数，和改变权重后到周期结束的选择序列
+    let cases: [(ChangePolicy, usize, &[usize]); 5] = [
+        (ChangePolicy::Immediate, 2, &[2, 0, 2, 0, 2, 0, 0, 0]),
+        (ChangePolicy::Immediate, 1, &[2, 0, 2, 0, 2, 0, 0, 0]),
+        (ChangePolicy::NextRound, 2, &[2, 0, 2, 0, 2, 0, 0, 0]),
+        (ChangePolicy::NextRound, 1, &[1, 2, 0, 2, 0, 2, 0, 0, 0]),
+        (ChangePolicy::NextCycle, 2, &[2, 1, 2, 1, 1]),
+    ];
+    for (policy, picked, expected) in cases {
+        let mut selector = IWRRSelector::new([1, 4, 2]);
+        selector.set_change_policy(policy);
+        assert_eq!(selector.change_policy(), policy);
+
+        //在周期中改变权重
+        for _ in 0..picked {
+            selector.select();
+        }
+        assert_eq!(selector.change_weight(0, 6), Some(1));
+        assert_eq!(selector.change_weight(1, 0), Some(4));
+        assert_eq!(selector.change_weight(2, 3), Some(2));
+        assert_eq!(selector.try_weight(1), Some(0));
+        match policy {
+            ChangePolicy::Immediate => {
+                assert_eq!(selector.try_active_weight(1), Some(0));
+                assert_eq!(selector.max_weight(), 6);
+            },
+            _ => {
+                assert_eq!(selector.try_active_weight(1), Some(4));
+                assert_eq!(selector.max_weight(), 4);
+            },
+        }
+
+        //立即生效在当前轮次使用新的权重，下一轮生效在当前轮次结束后使用新的权重，下一周期生效在当前周期结束后使用新的权重
+        let sequence: Vec<usize> = (0..expected.len()).map(|_| selector.select()).collect();
+        assert_eq!(sequence, expected, "policy: {:?}, picked: {}", policy, picked);
+
+        //之后的每个周期的选择次数与新的权重相同
+        assert_cycle_counts(&mut selector, [6, 0, 3], 3);
+        assert_eq!(selector.try_active_weight(1), Some(0));
+        assert_eq!(selector.max_weight(), 6);
+    }
+
+    //当前没有可以被选择的位置时，未生效的权重立即生效
+    let mut selector = IWRRSelector::new([1, 1]);
+    selector.set_change_policy(ChangePolicy::NextCycle);
+    selector.change_weight(0, 0);
+    selector.change_weight(1, 0);
+    assert_eq!(selector.try_select(), Some(0));
+    assert_eq!(selector.try_select(), Some(1));
+    assert_eq!(selector.try_select(), None);
+    selector.change_weight(1, 2);
+    assert_eq!(selector.try_select(), Some(1));
+
+    //切换为立即生效时，未生效的权重立即生效
+    selector.change_weight(0, 3);
+    assert_eq!(selector.try_active_weight(0), Some(0));
+    selector.set_change_policy(ChangePolicy::Immediate);
+    assert_eq!(selector.try_active_weight(0), Some(3));
+}
+
+#[test]
+fn test_dynamic() {
+    const CYCLES: usize = 10;
+
+    let mut selector = DynIWRRSelector::new();
+    assert!(selector.is_empty());
+    assert_eq!(selector.try_select(), None);
+
+    let x = selector.push(3);
+    let y = selector.push(2);
+    let z = selector.push(1);
+    assert_eq!(selector.len(), 3);
+    assert_eq!(selector.max_weight(), 3);
+
+    //与交替加权轮询选择器的选择顺序相同
+    let mut fixed = IWRRSelector::new([3, 2, 1]);
+    let ids = [x, y, z];
+    for _ in 0..6 * CYCLES {
+        assert_eq!(selector.select(), ids[fixed.select()]);
+    }
+
+    //在周期中移除位置，其它位置不会被跳过或重复选择
+    assert_eq!(selector.select(), x);
+    assert_eq!(selector.remove(y), Some(2));
+    assert_eq!(selector.remove(y), None);
+    assert!(!selector.contains(y));
+    assert_eq!(selector.len(), 2);
+    let picks: Vec<_> = (0..3).map(|_| selector.select()).collect();
+    assert_eq!(picks, vec![z, x, x]);
+    for _ in 0..CYCLES {
+        let picks: Vec<_> = (0..4).map(|_| selector.select()).collect();
+        assert_eq!(picks, vec![x, z, x, x]);
+    }
+
+    //被移除位置的下标可以被复用，但位置的唯一标识不同
+    let w = selector.push(2);
+    assert_eq!(w.index(), y.index());
+    assert_ne!(w, y);
+    assert_eq!(selector.try_weight(y), None);
+    assert_eq!(selector.try_weight(w), Some(2));
+    assert_eq!(selector.change_weight(y, 1), None);
+    assert_eq!(selector.change_weight(w, u8::MAX), None);
+    assert_eq!(selector.iter().collect::<Vec<_>>(), vec![(x, 3), (w, 2), (z, 1)]);
+
+    //移除最大权重的位置后重新计算最大的权重
+    assert_eq!(selector.remove(x), Some(3));
+    assert_eq!(selector.max_weight(), 2);
+    selector.reset();
+    for _ in 0..CYCLES {
+        let picks: Vec<_> = (0..3).map(|_| selector.select()).collect();
+        assert_eq!(picks, vec![w, z, w]);
+    }
+
+    selector.remove(w);
+    selector.remove(z);
+    assert!(selector.is_empty());
+    assert_eq!(selector.eligible_len(), 0);
+    assert_eq!(selector.try_select(), None);
+
+    let mut selector = GenericDynIWRRSelector::<u32>::with_capacity(2);
+    assert_eq!(selector.try_push(u32::MAX),
+               Err(WrrError::InvalidWeight { index: 0, weight: u32::MAX as u64 }));
+    let x = selector.push(1000);
+    assert_eq!(selector.select(), x);
+}
+
+#[test]
+fn test_weighted_pool() {
+    const CYCLES: usize = 10;
+
+    let mut pool = WeightedPool::new();
+    assert!(pool.is_empty());
+    assert_eq!(pool.next(), None);
+
+    let x = pool.insert("x", 3);
+    let y = pool.insert("y", 2);
+    let z = pool.insert("z", 1);
+    assert_eq!(pool.len(), 3);
+    assert_eq!(pool.get(y), Some(&"y"));
+    assert_eq!(pool.get_weight(x), Some(3));
+
+    //与交替加权轮询选择器的选择顺序相同
+    let mut selector = IWRRSelector::new([3, 2, 1]);
+    let items = ["x", "y", "z"];
+    for _ in 0..6 * CYCLES {
+        assert_eq!(pool.next(), Some(&items[selector.select()]));
+    }
+
+    //改变成员的权重
+    assert_eq!(pool.set_weight(y, 0), Some(2));
+    assert_eq!(pool.set_weight(z, u8::MAX), None);
+    pool.reset();
+    for _ in 0..CYCLES {
+        let picks: Vec<_> = (0..4).map(|_| *pool.next().unwrap()).collect();
+        assert_eq!(picks, vec!["x", "z", "x", "x"]);
+    }
+
+    //移除成员后，被移除成员的唯一标识失效
+    assert_eq!(pool.remove(x), Some("x"));
+    assert_eq!(pool.remove(x), None);
+    assert_eq!(pool.get(x), None);
+    assert_eq!(pool.get_weight(x), None);
+    let w = pool.insert("w", 1);
+    assert_eq!(pool.get(x), None);
+    assert_eq!(pool.get(w), Some(&"w"));
+    assert_eq!(pool.iter().collect::<Vec<_>>(), vec![(w, &"w", 1), (y, &"y", 0), (z, &"z", 1)]);
+
+    //通过可写引用修改成员
+    for (_, item, _) in pool.iter_mut() {
+        *item = "v";
+    }
+    *pool.next_mut().unwrap() = "u";
+    assert_eq!(pool.get(w), Some(&"u"));
+    assert_eq!(pool.next_with_id(), Some((z, &"v")));
+}
+
+#[test]
+fn test_atomic() {
+    const THREADS: usize = 8;
+    const CYCLES: usize = 10000;
+
+    let weights = [18, 16, 12, 14, 8, 10, 4, 6, 2, 1];
+    let cycle_len: usize = weights.iter().map(|weight| *weight as usize).sum();
+
+    //单线程时与交替加权轮询选择器的选择顺序相同
+    let selector = AtomicIWRRSelector::new(weights);
+    let mut fixed = IWRRSelector::new(weights);
+    for _ in 0..cycle_len * 10 {
+        assert_eq!(selector.select(), fixed.select());
+    }
+    selector.reset();
+
+    //多线程竞争时，所有线程的选择次数之和与权重完全相同
+    let selector = Arc::new(selector);
+    let mut handles = Vec::with_capacity(THREADS);
+    for _ in 0..THREADS {
+        let selector = selector.clone();
+        handles.push(thread::spawn(move || {
+            let mut counts = [0usize; 10];
+            for _ in 0..cycle_len * CYCLES / THREADS {
+                counts[selector.select()] += 1;
+            }
+            counts
+        }));
+    }
+
+    let mut counts = [0usize; 10];
+    for handle in handles {
+        for (count, thread_count) in counts.iter_mut().zip(handle.join().unwrap().iter()) {
+            *count += *thread_count;
+        }
+    }
+    for (count, weight) in counts.iter().zip(weights.iter()) {
+        assert_eq!(*count, *weight as usize * CYCLES);
+    }
+
+    //多线程竞争时，每批整数个周期的选择次数与权重完全相同
+    let selector = Arc::new(AtomicIWRRSelector::new([3, 2, 1]));
+    for batch in 1..=10 {
+        let mut handles = Vec::with_capacity(THREADS);
+        for _ in 0..THREADS {
+            let selector = selector.clone();
+            handles.push(thread::spawn(move || {
+                let mut counts = [0usize; 3];
+                for _ in 0..6 * batch {
+                    counts[selector.select()] += 1;
+                }
+                counts
+            }));
+        }
+
+        let mut counts = [0usize; 3];
+        for handle in handles {
+            for (count, thread_count) in counts.iter_mut().zip(handle.join().unwrap().iter()) {
+                *count += *thread_count;
+            }
+        }
+        assert_eq!(counts, [3 * batch * THREADS, 2 * batch * THREADS, batch * THREADS]);
+    }
+
+    let mut selector = AtomicIWRRSelector::new([1, 1]);
+    assert_eq!(selector.change_weight(0, 3), Some(1));
+    assert_eq!(selector.max_weight(), 3);
+    assert_eq!(selector.change_weight(0, 0), Some(3));
+    assert_eq!(selector.change_weight(1, 0), Some(1));
+    assert_eq!(selector.try_select(), None);
+    assert_eq!(selector.change_weight(2, 1), None);
+}
+
+#[test]
+fn test_nth_in_cycle() {
+    assert_nth_in_cycle(IWRRSelector::new([1, 0]));
+    assert_nth_in_cycle(IWRRSelector::new([2, 1]));
+    assert_nth_in_cycle(IWRRSelector::new([254, 1]));
+    assert_nth_in_cycle(IWRRSelector::new([6, 3, 1]));
+    assert_nth_in_cycle(IWRRSelector::new([0, 18, 16, 12, 14, 8, 10, 4, 6, 2, 1, 0]));
+    assert_nth_in_cycle(IWRRSelectorByWider::new([18, 16, 12, 14, 8, 10, 4, 6, 2, 1]));
+
+    let selector = IWRRSelector::new([18, 16, 12, 14, 8, 10, 4, 6, 2, 1]);
+    assert_eq!(selector.cycle_len(), 91);
+
+    let selector = IWRRSelectorByWider::new([usize::MAX - 1, usize::MAX - 1]);
+    assert_eq!(selector.cycle_len(), usize::MAX);
+    assert_eq!(selector.nth_in_cycle(0), 0);
+    assert_eq!(selector.nth_in_cycle(usize::MAX - 2), 1);
+}
+
+// 检查第n次选择的位置和定位后的选择，与逐次选择的结果相同
+fn assert_nth_in_cycle<W: Weight, const LEN: usize>(selector: GenericIWRRSelector<W, LEN>) {
+    let cycle_len = selector.cycle_len();
+    let mut sequential = selector.clone();
+    let picks: Vec<usize> = (0..cycle_len * 3).map(|_| sequential.select()).collect();
+
+    for (n, pick) in picks.iter().enumerate() {
+        assert_eq!(selector.nth_in_cycle(n), *pick);
+
+        let mut seeked = selector.clone();
+        seeked.seek(n);
+        for pick in &picks[n..(n + cycle_len).min(picks.len())] {
+            assert_eq!(seeked.select(), *pick);
+        }
+    }
+}
+
+#[test]
+fn test_lookahead() {
+    let mut selector = IWRRSelector::new([3, 2, 1]);
+    assert_eq!(selector.schedule(), vec![0, 1, 2, 0, 1, 0]);
+
+    //预览不会改变选择器的状态
+    selector.select();
+    selector.select();
+    let (pos, round) = (selector.pos(), selector.round());
+    assert_eq!(selector.peek(), 2);
+    assert_eq!(selector.upcoming().take(8).collect::<Vec<_>>(), vec![2, 0, 1, 0, 0, 1, 2, 0]);
+    assert_eq!(selector.schedule(), vec![0, 1, 2, 0, 1, 0]);
+    assert_eq!((selector.pos(), selector.round()), (pos, round));
+
+    //预览的结果与之后的选择结果完全相同
+    let upcoming: Vec<_> = selector.upcoming().take(100).collect();
+    for pick in upcoming {
+        assert_eq!(selector.select(), pick);
+    }
+
+    //未生效的权重在预览中按生效策略生效
+    selector.set_change_policy(ChangePolicy::NextCycle);
+    selector.change_weight(2, 0);
+    let upcoming: Vec<_> = selector.upcoming().take(20).collect();
+    assert_eq!(selector.schedule(), vec![0, 1, 0, 1, 0]);
+    for pick in upcoming {
+        assert_eq!(selector.select(), pick);
+    }
+
+    selector.change_weight(0, 0);
+    selector.change_weight(1, 0);
+    selector.reset();
+    assert_eq!(selector.try_peek(), None);
+    assert_eq!(selector.upcoming().next(), None);
+    assert!(selector.schedule().is_empty());
+}
+
+#[test]
+fn test_iterator() {
+    let mut selector = IWRRSelector::new([3, 2, 1]);
+
+    //可以与迭代器适配器组合
+    let picks: Vec<usize> = selector.iter_mut().take(8).collect();
+    assert_eq!(picks, vec![0, 1, 2, 0, 1, 0, 0, 1]);
+    let names: Vec<(usize, &str)> = (0..3).zip(&mut selector).map(|(n, pick)| (n, ["x", "y", "z"][pick])).collect();
+    assert_eq!(names, vec![(0, "z"), (1, "x"), (2, "y")]);
+
+    //从任意状态开始连续选择一个周期，每个位置被选择的次数都与权重相同
+    for _ in 0..10 {
+        let mut counts = [0; 3];
+        for pick in selector.take_cycle() {
+            counts[pick] += 1;
+        }
+        assert_eq!(counts, [3, 2, 1]);
+        selector.select();
+    }
+
+    //持有选择器的迭代器
+    let mut iter = IWRRSelectorByWider::new([1, 2]).into_iter();
+    assert_eq!(iter.by_ref().take(3).collect::<Vec<_>>(), vec![0, 1, 1]);
+    let mut selector = iter.into_inner();
+    assert_eq!(selector.take_cycle().collect::<Vec<_>>(), vec![0, 1, 1]);
+
+    //所有权重都为零时迭代结束
+    selector.change_weight(0, 0);
+    selector.change_weight(1, 0);
+    assert_eq!(selector.iter_mut().next(), None);
+    assert_eq!(selector.take_cycle().count(), 0);
+}
+
+#[test]
+fn test_select_batch() {
+    let mut selector = IWRRSelector::new([3, 2, 1]);
+    let mut buf = [0; 8];
+    assert_eq!(selector.select_into(&mut buf), 8);
+    assert_eq!(buf, [0, 1, 2, 0, 1, 0, 0, 1]);
+
+    assert_select_counts(IWRRSelector::new([1, 0]));
+    assert_select_counts(IWRRSelector::new([2, 1]));
+    assert_select_counts(IWRRSelector::new([254, 1]));
+    assert_select_counts(IWRRSelector::new([6, 3, 1]));
+    assert_select_counts(IWRRSelector::new([0, 18, 16, 12, 14, 8, 10, 4, 6, 2, 1, 0]));
+    assert_select_counts(IWRRSelectorByWider::new([18, 16, 12, 14, 8, 10, 4, 6, 2, 1]));
+
+    //降低最大的权重后，当前轮数可能大于最大的权重
+    let mut selector = IWRRSelector::new([6, 3, 1]);
+    selector.seek(9);
+    selector.change_weight(0, 2);
+    assert_select_counts(selector);
+
+    //有未生效的权重时，按生效策略生效
+    let mut selector = IWRRSelector::new([6, 3, 1]);
+    selector.set_change_policy(ChangePolicy::NextCycle);
+    selector.seek(4);
+    selector.change_weight(2, 5);
+    assert_select_counts(selector);
+    let mut selector = IWRRSelector::new([6, 3, 1]);
+    selector.set_change_policy(ChangePolicy::NextRound);
+    selector.seek(4);
+    selector.change_weight(0, 1);
+    selector.change_weight(1, 7);
+    assert_select_counts(selector);
+
+    //有被提前连续选择的位置时，跳过的次数按逐次选择生效
+    let mut selector = IWRRSelector::new([6, 3, 1]);
+    selector.select();
+    assert_eq!(selector.select_run(4), (1, 3));
+    assert_select_counts(selector);
+
+    //所有权重都为零时不会选择
+    let mut selector = IWRRSelector::new([1, 1]);
+    selector.change_weight(0, 0);
+    selector.change_weight(1, 0);
+    assert_eq!(selector.select_counts(10), [0, 0]);
+    assert_eq!(selector.select_into(&mut buf), 0);
+}
+
+// 检查从不同的状态开始批量选择不同的次数，与逐次选择的结果和状态完全相同
+fn assert_select_counts<W: Weight, const LEN: usize>(selector: GenericIWRRSelector<W, LEN>) {
+    let cycle_len = selector.cycle_len();
+    for skip in 0..cycle_len + 2 {
+        for n in [0, 1, 2, cycle_len - 1, cycle_len, cycle_len + 1, cycle_len * 3 + skip, 10007] {
+            let mut batch = selector.clone();
+            let mut sequential = selector.clone();
+            for _ in 0..skip {
+                batch.select();
+                sequential.select();
+            }
+
+            let mut counts = [0; LEN];
+            for _ in 0..n {
+                counts[sequential.select()] += 1;
+            }
+            assert_eq!(batch.select_counts(n), counts);
+            assert_eq!((batch.pos(), batch.round()), (sequential.pos(), sequential.round()));
+            assert_eq!(batch.select(), sequential.select());
+        }
+    }
+}
+
+#[test]
+fn test_select_run() {
+    const CYCLES: usize = 10;
+
+    let mut selector = IWRRSelector::new([5, 3, 1]);
+    let runs: Vec<_> = (0..4).map(|_| selector.select_run(usize::MAX)).collect();
+    assert_eq!(runs, vec![(0, 5), (1, 3), (2, 1), (0, 5)]);
+
+    //最大连续次数限制连续选择的次数
+    selector.reset();
+    let runs: Vec<_> = (0..6).map(|_| selector.select_run(2)).collect();
+    assert_eq!(runs, vec![(0, 2), (1, 2), (2, 1), (0, 2), (1, 1), (0, 1)]);
+    assert_eq!(selector.select_run(0), (0, 1));
+    assert_eq!(selector.select_run(1), (1, 1));
+
+    //每个周期中每个位置被选择的次数仍然与权重相同
+    let weights = [18, 16, 12, 14, 8, 10, 4, 6, 2, 1];
+    let cycle_len: usize = weights.iter().map(|weight| *weight as usize).sum();
+    for max_run in [1, 2, 3, 7, 100] {
+        let mut selector = IWRRSelector::new(weights);
+        for _ in 0..CYCLES {
+            let mut counts = [0; 10];
+            let mut total = 0;
+            while total < cycle_len {
+                let (pos, run) = selector.select_run(max_run);
+                assert!(run >= 1 && run <= max_run);
+                counts[pos] += run;
+                total += run;
+            }
+            assert_eq!(total, cycle_len);
+            for (count, weight) in counts.iter().zip(weights.iter()) {
+                assert_eq!(*count, *weight as usize);
+            }
+
+            //与逐次选择混合使用
+            let mut counts = [0; 10];
+            let mut total = 0;
+            while total < cycle_len {
+                let (pos, run) = selector.select_run(max_run);
+                counts[pos] += run;
+                counts[selector.select()] += 1;
+                total += run + 1;
+            }
+            let rest = cycle_len * 2 - total;
+            let mut batch = selector.clone();
+            for _ in 0..rest {
+                counts[selector.select()] += 1;
+            }
+            for (count, weight) in counts.iter().zip(weights.iter()) {
+                assert_eq!(*count, *weight as usize * 2);
+            }
+
+            //批量选择的结果与逐次选择相同
+            let mut sequential = batch.clone();
+            let mut counts = [0; 10];
+            for _ in 0..cycle_len + 3 {
+                counts[sequential.select()] += 1;
+            }
+            assert_eq!(batch.select_counts(cycle_len + 3), counts);
+        }
+    }
+}
+
+#[test]
+fn test_normalized() {
+    let mut selector = IWRRSelector::new([200, 100]);
+    assert!(!selector.is_normalized());
+    assert_eq!(selector.cycle_len(), 300);
+
+    //约简后份额不变，但周期更短，获取的权重仍然是未约简的权重
+    selector.set_normalized(true);
+    assert!(selector.is_normalized());
+    assert_eq!(selector.effective_weights(), [2, 1]);
+    assert_eq!(selector.cycle_len(), 3);
+    assert_eq!(selector.max_weight(), 2);
+    assert_eq!(selector.try_weight(0), Some(200));
+    assert_eq!(selector.try_active_weight(0), Some(2));
+    assert_eq!(selector.take_cycle().collect::<Vec<_>>(), vec![0, 1, 0]);
+
+    //改变权重后按新的最大公约数约简
+    assert_eq!(selector.change_weight(1, 50), Some(100));
+    assert_eq!(selector.effective_weights(), [4, 1]);
+    selector.change_weight(0, 0);
+    assert_eq!(selector.effective_weights(), [0, 1]);
+    selector.change_weight(0, 75);
+    assert_eq!(selector.effective_weights(), [3, 2]);
+    assert_eq!(selector.eligible_len(), 2);
+    selector.reset();
+    assert_cycle_counts(&mut selector, [3, 2], 3);
+
+    //约简按改变权重的生效策略生效
+    selector.set_change_policy(ChangePolicy::NextCycle);
+    selector.select();
+    selector.set_normalized(false);
+    assert_eq!(selector.effective_weights(), [3, 2]);
+    for _ in 0..4 {
+        selector.select();
+    }
+    assert_eq!(selector.effective_weights(), [3, 2]);
+    selector.select();
+    assert_eq!(selector.effective_weights(), [75, 50]);
+    assert_eq!(selector.cycle_len(), 125);
+
+    let mut selector = IWRRSelectorByWider::new([6000, 4000, 0, 2000]);
+    selector.set_normalized(true);
+    assert_eq!(selector.effective_weights(), [3, 2, 0, 1]);
+    let mut counts = [0usize; 4];
+    for _ in 0..600 {
+        counts[selector.select()] += 1;
+    }
+    assert_eq!(counts, [300, 200, 0, 100]);
+}
+
+#[test]
+fn test_available() {
+    let mut selector = IWRRSelector::new([3, 2, 1]);
+    assert!(selector.is_available(1));
+    assert!(!selector.is_available(3));
+    assert_eq!(selector.set_available(1, false), Some(true));
+    assert_eq!(selector.set_available(1, false), Some(false));
+    assert_eq!(selector.set_available(3, false), None);
+    assert_eq!(selector.unavailable_len(), 1);
+
+    //不可用的位置被跳过，其它位置仍然保持权重的比例
+    let mut counts = [0usize; 3];
+    for _ in 0..40 {
+        counts[selector.select()] += 1;
+    }
+    assert_eq!(counts, [30, 0, 10]);
+
+    let mut counts = [0usize; 3];
+    for _ in 0..40 {
+        counts[selector.select_where(|index| index != 0)] += 1;
+    }
+    assert_eq!(counts, [0, 0, 40]);
+
+    //没有可用的位置则返回空，并从新的周期开始下一次选择
+    assert_eq!(selector.try_select_where(|index| index == 1), None);
+    assert_eq!((selector.pos(), selector.round()), (0, 0));
+    assert_eq!(selector.set_available(1, true), Some(false));
+    assert_eq!(selector.unavailable_len(), 0);
+    assert_cycle_counts(&mut selector, [3, 2, 1], 3);
+
+    //满足条件的选择序列与不限制条件的选择序列中满足条件的部分相同
+    let weights = [18, 16, 12, 14, 8, 10, 4, 6, 2, 1];
+    let cycle_len: usize = weights.iter().map(|weight| *weight as usize).sum();
+    for mask in [0b1u32, 0b10_0000_0001, 0b11_1111_1110, 0b01_0101_0100, 0b10_0010_0000] {
+        let available = |index: usize| mask & (1 << index) != 0;
+
+        let mut selector = IWRRSelector::new(weights);
+        let mut masked = IWRRSelector::new(weights);
+        for index in 0..weights.len() {
+            masked.set_available(index, available(index));
+        }
+        let mut filtered = selector.clone();
+
+        let expected: Vec<_> = (0..cycle_len * 3)
+            .map(|_| selector.select())
+            .filter(|index| available(*index))
+            .collect();
+        let actual: Vec<_> = (0..expected.len())
+            .map(|_| masked.select())
+            .collect();
+        assert_eq!(actual, expected);
+        let actual: Vec<_> = (0..expected.len())
+            .map(|_| filtered.select_where(available))
+            .collect();
+        assert_eq!(actual, expected);
+    }
+
+    //部分位置不可用时，调度表仍然是完整的周期，一个周期的迭代只包含可用的位置
+    let mut selector = IWRRSelector::new([2, 1]);
+    selector.set_available(1, false);
+    assert_eq!(selector.schedule(), vec![0, 1, 0]);
+    assert_eq!(selector.take_cycle().collect::<Vec<_>>(), vec![0, 0]);
+    assert_eq!(selector.take_cycle().collect::<Vec<_>>(), vec![0, 0]);
+    assert!(!selector.is_available(1));
+
+    //所有位置都不可用时，调度表不会panic，一个周期的迭代为空
+    selector.set_available(0, false);
+    assert_eq!(selector.schedule(), vec![0, 1, 0]);
+    assert_eq!(selector.take_cycle().count(), 0);
+}
+
+#[test]
+#[should_panic(expected = "no available slot")]
+fn test_select_no_available_slot() {
+    let mut selector = IWRRSelector::new([1, 2]);
+    selector.set_available(0, false);
+    selector.set_available(1, false);
+    selector.select();
+}
+
+#[test]
+fn test_weighted_queues() {
+    let mut queues = WeightedQueues::new([3, 2, 1]);
+    assert!(queues.is_empty());
+    assert_eq!(queues.classes(), 3);
+    assert_eq!(queues.pop(), None);
+
+    for n in 0..6 {
+        queues.push(0, n);
+        queues.push(1, n + 10);
+        queues.push(2, n + 20);
+    }
+    assert_eq!(queues.len(), 18);
+    assert_eq!(queues.len_of(1), 6);
+    assert_eq!(queues.len_of(3), 0);
+    assert_eq!(queues.try_push(3, 0), Err(WrrError::IndexOutOfRange { index: 3, len: 3 }));
+
+    //每个队列先进先出，并按交替加权轮询弹出
+    let popped: Vec<_> = (0..6).map(|_| queues.pop().unwrap()).collect();
+    assert_eq!(popped, vec![(0, 0), (1, 10), (2, 20), (0, 1), (1, 11), (0, 2)]);
+
+    //空队列被跳过，不为空的队列之间仍然保持权重的比例
+    let mut counts = [0usize; 3];
+    for _ in 0..9 {
+        counts[queues.pop().unwrap().0] += 1;
+    }
+    assert_eq!(counts, [3, 4, 2]);
+    assert_eq!(queues.len_of(0), 0);
+    let mut counts = [0usize; 3];
+    while let Some((class, _)) = queues.pop() {
+        counts[class] += 1;
+    }
+    assert_eq!(counts, [0, 0, 3]);
+    assert!(queues.is_empty());
+    assert_eq!(queues.pop(), None);
+
+    let mut queues = GenericWeightedQueues::<_, usize, 3>::new([300, 200, 0]);
+    for n in 0..1000 {
+        queues.push(n % 3, n);
+    }
+    let mut counts = [0usize; 3];
+    for _ in 0..500 {
+        counts[queues.pop().unwrap().0] += 1;
+    }
+    assert_eq!(counts, [300, 200, 0]);
+
+    //权重为零的队列不会被弹出，改变权重后被弹出
+    while queues.len_of(0) + queues.len_of(1) > 0 {
+        queues.pop().unwrap();
+    }
+    assert_eq!(queues.pop(), None);
+    assert_eq!(queues.len(), 333);
+    assert_eq!(queues.change_weight(2, 1), Some(0));
+    assert_eq!(queues.pop(), Some((2, 2)));
+    assert_eq!(queues.pop_of(2), Some(5));
+    queues.clear();
+    assert!(queues.is_empty());
+    assert_eq!(queues.len_of(2), 0);
+}
+
+#[test]
+fn test_sync_weighted_queues() {
+    let queues = SyncWeightedQueues::new([3, 2, 1]);
+    let mut expected = WeightedQueues::new([3, 2, 1]);
+    assert_eq!(queues.pop(), None);
+    assert_eq!(queues.try_push(3, 0), Err(WrrError::IndexOutOfRange { index: 3, len: 3 }));
+
+    //与加权多队列的弹出顺序相同
+    for n in 0..30 {
+        queues.push(n % 3, n);
+        expected.push(n % 3, n);
+    }
+    assert_eq!(queues.len(), 30);
+    assert_eq!(queues.len_of(2), 10);
+    for _ in 0..15 {
+        assert_eq!(queues.pop(), expected.pop());
+    }
+    assert_eq!(queues.change_weight(0, 0), Some(3));
+    assert_eq!(expected.change_weight(0, 0), Some(3));
+    while let Some(item) = expected.pop() {
+        assert_eq!(queues.pop(), Some(item));
+    }
+    assert_eq!(queues.pop(), None);
+    assert_eq!(queues.len(), queues.len_of(0));
+
+    //超时则返回空
+    let now = Instant::now();
+    assert_eq!(queues.pop_wait(Duration::from_millis(50)), None);
+    assert!(now.elapsed() >= Duration::from_millis(50));
+
+    //增加成员后唤醒等待的消费者
+    let queues = Arc::new(queues);
+    let producer = {
+        let queues = queues.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            queues.push(1, 100);
+        })
+    };
+    assert_eq!(queues.pop_wait(Duration::from_secs(10)), Some((1, 100)));
+    producer.join().unwrap();
+
+    //释放时释放所有未弹出的成员
+    let item = Arc::new(());
+    let queues = SyncWeightedQueues::new([1, 1]);
+    for n in 0..10 {
+        queues.push(n % 2, item.clone());
+    }
+    queues.pop().unwrap();
+    assert_eq!(Arc::strong_count(&item), 10);
+    drop(queues);
+    assert_eq!(Arc::strong_count(&item), 1);
+}
+
+#[test]
+fn test_sync_weighted_queues_stress() {
+    const PRODUCERS: usize = 4;
+    const COUNT: usize = 20000;
+
+    let queues = Arc::new(SyncWeightedQueues::new([4, 2, 1]));
+    let producers: Vec<_> = (0..PRODUCERS).map(|producer| {
+        let queues = queues.clone();
+        thread::spawn(move || {
+            for n in 0..COUNT {
+                queues.push(n % 3, (producer, n));
+            }
+        })
+    }).collect();
+
+    //每个生产者增加到每个类别的成员都按增加的顺序弹出
+    let mut nexts = [[0usize; 3]; PRODUCERS];
+    let mut counts = [0usize; 3];
+    for _ in 0..PRODUCERS * COUNT {
+        let (class, (producer, n)) = queues.pop_wait(Duration::from_secs(10)).unwrap();
+        assert_eq!(n % 3, class);
+        assert_eq!(n, nexts[producer][class] * 3 + class);
+        nexts[producer][class] += 1;
+        counts[class] += 1;
+    }
+    for producer in producers {
+        producer.join().unwrap();
+    }
+    assert_eq!(queues.pop(), None);
+    assert!(queues.is_empty());
+    let total: usize = counts.iter().sum();
+    assert_eq!(total, PRODUCERS * COUNT);
+
+    //所有队列都不为空时按权重的比例弹出
+    let producers: Vec<_> = (0..PRODUCERS).map(|producer| {
+        let queues = queues.clone();
+        thread::spawn(move || {
+            for n in 0..COUNT {
+                queues.push(n % 3, (producer, n));
+            }
+        })
+    }).collect();
+    for producer in producers {
+        producer.join().unwrap();
+    }
+    let mut counts = [0usize; 3];
+    for _ in 0..7000 {
+        counts[queues.pop().unwrap().0] += 1;
+    }
+    assert_eq!(counts, [4000, 2000, 1000]);
+}
+
+#[test]
+fn test_sync_weighted_queues_lens() {
+    const COUNT: usize = 20000;
+
+    //其它类别的队列不为空时，与生产者并发弹出，成员数量也不会小于零
+    let queues = Arc::new(SyncWeightedQueues::new([1, 1]));
+    for n in 0..COUNT {
+        queues.push(1, n);
+    }
+    let producer = {
+        let queues = queues.clone();
+        thread::spawn(move || {
+            for n in 0..COUNT {
+                queues.push(0, n);
+            }
+        })
+    };
+
+    let mut popped = 0;
+    while popped < COUNT * 2 {
+        assert!(queues.len() <= COUNT * 2 - popped);
+        assert!(queues.len_of(0) <= COUNT);
+        assert!(queues.len_of(1) <= COUNT);
+        if queues.pop().is_some() {
+            popped += 1;
+        }
+    }
+    producer.join().unwrap();
+    assert!(queues.is_empty());
+    assert_eq!(queues.len_of(0), 0);
+    assert_eq!(queues.len_of(1), 0);
+}
+
+#[test]
+fn test_weighted_receiver() {
+    let (control, control_receiver) = channel();
+    let (user, user_receiver) = channel();
+    let (background, background_receiver) = channel();
+    let mut receiver = WeightedReceiver::new([control_receiver, user_receiver, background_receiver],
+                                             [3, 2, 1]);
+    assert_eq!(receiver.len(), 3);
+    assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+
+    //按权重选择通道，每个通道的消息先进先出
+    for n in 0..12 {
+        control.send(n).unwrap();
+        user.send(n + 100).unwrap();
+        background.send(n + 200).unwrap();
+    }
+    let received: Vec<_> = (0..6).map(|_| receiver.try_recv().unwrap()).collect();
+    assert_eq!(received, vec![(0, 0), (1, 100), (2, 200), (0, 1), (1, 101), (0, 2)]);
+
+    //空的通道被跳过，其它通道仍然保持权重的比例
+    let mut counts = [0usize; 3];
+    for _ in 0..18 {
+        counts[receiver.try_recv().unwrap().0] += 1;
+    }
+    assert_eq!(counts, [9, 6, 3]);
+    assert_eq!(receiver.try_recv(), Ok((1, 108)));
+    assert_eq!(receiver.try_recv(), Ok((2, 204)));
+    assert_eq!(receiver.try_recv(), Ok((1, 109)));
+
+    //已断开的通道在所有消息都被接收后被跳过
+    drop(user);
+    assert_eq!(receiver.is_disconnected(1), Some(false));
+    let mut counts = [0usize; 3];
+    while let Ok((index, _)) = receiver.try_recv() {
+        counts[index] += 1;
+    }
+    assert_eq!(counts, [0, 2, 7]);
+    assert_eq!(receiver.is_disconnected(1), Some(true));
+    assert_eq!(receiver.is_disconnected(3), None);
+    assert_eq!(receiver.connected_len(), 2);
+
+    //阻塞接收，超时则返回超时错误
+    let now = Instant::now();
+    assert_eq!(receiver.recv_timeout(Duration::from_millis(50)), Err(RecvTimeoutError::Timeout));
+    assert!(now.elapsed() >= Duration::from_millis(50));
+    let sender = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        background.send(300).unwrap();
+    });
+    assert_eq!(receiver.recv(), Ok((2, 300)));
+    sender.join().unwrap();
+
+    //只有一个可以等待的通道时，在这个通道上一直阻塞等待
+    let (single, single_receiver) = channel();
+    let mut single_receiver = WeightedReceiver::new([single_receiver], [1]);
+    let now = Instant::now();
+    assert_eq!(single_receiver.recv_timeout(Duration::from_millis(50)), Err(RecvTimeoutError::Timeout));
+    assert!(now.elapsed() >= Duration::from_millis(50));
+    let sender = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        single.send(400).unwrap();
+    });
+    assert_eq!(single_receiver.recv(), Ok((0, 400)));
+    sender.join().unwrap();
+    assert_eq!(single_receiver.recv(), Err(RecvError));
+
+    //权重为零的通道不会被接收，其它通道都已断开时返回断开错误
+    let (first, first_receiver) = channel();
+    let (second, second_receiver) = channel();
+    let mut zero_receiver = WeightedReceiver::new([first_receiver, second_receiver], [1, 0]);
+    assert_eq!(zero_receiver.connected_len(), 1);
+    drop(first);
+    second.send(500).unwrap();
+    drop(second);
+    assert_eq!(zero_receiver.try_recv(), Err(TryRecvError::Disconnected));
+    assert_eq!(zero_receiver.connected_len(), 0);
+    assert_eq!(zero_receiver.recv_timeout(Duration::from_millis(200)), Err(RecvTimeoutError::Disconnected));
+    assert_eq!(zero_receiver.recv(), Err(RecvError));
+
+    //改变权重后可以继续接收权重为零时未被接收的消息
+    assert_eq!(zero_receiver.change_weight(1, 1), Some(0));
+    assert_eq!(zero_receiver.connected_len(), 1);
+    assert_eq!(zero_receiver.recv(), Ok((1, 500)));
+    assert_eq!(zero_receiver.recv(), Err(RecvError));
+    assert_eq!(zero_receiver.is_disconnected(1), Some(true));
+
+    //已接收消息的通道的权重被改变为零后断开，同样返回断开错误
+    let (first, first_receiver) = channel();
+    let (second, second_receiver) = channel();
+    let mut zero_receiver = WeightedReceiver::new([first_receiver, second_receiver], [1, 1]);
+    second.send(600).unwrap();
+    assert_eq!(zero_receiver.try_recv(), Ok((1, 600)));
+    second.send(601).unwrap();
+    first.send(0).unwrap();
+    assert_eq!(zero_receiver.try_recv(), Ok((0, 0)));
+    assert_eq!(zero_receiver.change_weight(1, 0), Some(1));
+    drop(first);
+    drop(second);
+    assert_eq!(zero_receiver.try_recv(), Err(TryRecvError::Disconnected));
+    assert_eq!(zero_receiver.recv(), Err(RecvError));
+
+    //所有通道都已断开则返回错误
+    assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+    control.send(12).unwrap();
+    drop(control);
+    assert_eq!(receiver.recv(), Ok((0, 12)));
+    assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+    assert_eq!(receiver.recv(), Err(RecvError));
+    assert_eq!(receiver.recv_timeout(Duration::from_secs(10)), Err(RecvTimeoutError::Disconnected));
+    assert_eq!(receiver.connected_len(), 0);
+}
+
+// 手写的唤醒器的虚表，数据指针指向唤醒次数的计数器
+static COUNT_WAKER_VTABLE: RawWakerVTable = RawWakerVTable::new(
+    |data| RawWaker::new(data, &COUNT_WAKER_VTABLE),
+    |data| unsafe { (*(data as *const AtomicUsize)).fetch_add(1, Ordering::SeqCst); },
+    |data| unsafe { (*(data as *const AtomicUsize)).fetch_add(1, Ordering::SeqCst); },
+    |_| (),
+);
+
+// 构建唤醒时增加指定计数器的唤醒器
+fn count_waker(count: &'static AtomicUsize) -> Waker {
+    let data = count as *const AtomicUsize as *const ();
+    unsafe { Waker::from_raw(RawWaker::new(data, &COUNT_WAKER_VTABLE)) }
+}
+
+// 每次轮询都记录轮询次数并唤醒自己，轮询指定次数后完成的future
+struct Yield {
+    polls:  Arc<AtomicUsize>,
+    limit:  usize,
+}
+
+impl Future for Yield {
+    type Output = usize;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let polls = self.polls.fetch_add(1, Ordering::SeqCst) + 1;
+        if polls >= self.limit {
+            Poll::Ready(polls)
+        } else {
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+// 打开后完成的future
+#[derive(Clone, Default)]
+struct Gate(Arc<Mutex<(bool, Option<Waker>)>>);
+
+impl Gate {
+    fn open(&self) {
+        let waker = {
+            let mut gate = self.0.lock().unwrap();
+            gate.0 = true;
+            gate.1.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl Future for Gate {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut gate = self.0.lock().unwrap();
+        if gate.0 {
+            Poll::Ready(())
+        } else {
+            gate.1 = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+#[test]
+fn test_weighted_select() {
+    static WAKES: AtomicUsize = AtomicUsize::new(0);
+    let waker = count_waker(&WAKES);
+    let mut cx = Context::from_waker(&waker);
+
+    //一直就绪的子future按权重的比例被轮询
+    let polls: Vec<_> = (0..3).map(|_| Arc::new(AtomicUsize::new(0))).collect();
+    let mut select = WeightedSelect::new([
+        Box::pin(Yield { polls: polls[0].clone(), limit: 30 }),
+        Box::pin(Yield { polls: polls[1].clone(), limit: usize::MAX }),
+        Box::pin(Yield { polls: polls[2].clone(), limit: usize::MAX }),
+    ], [3, 2, 1]);
+    let mut pendings = 0;
+    let output = loop {
+        match Pin::new(&mut select).poll(&mut cx) {
+            Poll::Pending => pendings += 1,
+            Poll::Ready(output) => break output,
+        }
+    };
+    assert_eq!(output, Some((0, 30)));
+    assert_eq!(polls.iter().map(|polls| polls.load(Ordering::SeqCst)).collect::<Vec<_>>(),
+               vec![30, 20, 10]);
+    assert_eq!(WAKES.load(Ordering::SeqCst), pendings);
+    assert_eq!(select.len(), 2);
+    assert_eq!(select.is_terminated(0), Some(true));
+
+    //没有已就绪的子future则等待唤醒，并按交替加权轮询的顺序轮询已就绪的子future
+    let gates: Vec<Gate> = (0..3).map(|_| Gate::default()).collect();
+    let mut select = WeightedSelect::new([
+        Box::pin(gates[0].clone()) as Pin<Box<dyn Future<Output = ()>>>,
+        Box::pin(gates[1].clone()),
+        Box::pin(gates[2].clone()),
+    ], [1, 1, 1]);
+    let wakes = WAKES.load(Ordering::SeqCst);
+    assert_eq!(Pin::new(&mut select).poll(&mut cx), Poll::Pending);
+    assert_eq!(Pin::new(&mut select).poll(&mut cx), Poll::Pending);
+    assert_eq!(WAKES.load(Ordering::SeqCst), wakes);
+
+    gates[2].open();
+    gates[0].open();
+    assert_eq!(WAKES.load(Ordering::SeqCst), wakes + 2);
+    assert_eq!(Pin::new(&mut select).poll(&mut cx), Poll::Ready(Some((0, ()))));
+    assert_eq!(Pin::new(&mut select).poll(&mut cx), Poll::Ready(Some((2, ()))));
+    assert_eq!(Pin::new(&mut select).poll(&mut cx), Poll::Pending);
+
+    //替换已完成的子future
+    assert!(select.replace(0, Box::pin(future::ready(()))).is_none());
+    assert_eq!(select.len(), 2);
+    assert_eq!(Pin::new(&mut select).poll(&mut cx), Poll::Ready(Some((0, ()))));
+    gates[1].open();
+    assert_eq!(Pin::new(&mut select).poll(&mut cx), Poll::Ready(Some((1, ()))));
+    assert!(select.is_empty());
+    assert_eq!(Pin::new(&mut select).poll(&mut cx), Poll::Ready(None));
+
+    //权重为零的子future不会被轮询
+    let mut select = WeightedSelect::new([
+        Box::pin(future::ready(0usize)) as Pin<Box<dyn Future<Output = usize>>>,
+        Box::pin(future::ready(1)),
+    ], [0, 1]);
+    assert_eq!(Pin::new(&mut select).poll(&mut cx), Poll::Ready(Some((1, 1))));
+    assert_eq!(Pin::new(&mut select).poll(&mut cx), Poll::Pending);
+    assert_eq!(select.change_weight(0, 2), Some(0));
+    assert_eq!(Pin::new(&mut select).poll(&mut cx), Poll::Ready(Some((0, 0))));
+}
+
+static STATIC_SELECTOR: IWRRSelector<3> = IWRRSelector::new_const([5, 3, 1]);
+
+const CONST_SELECTOR: IWRRSelectorByWider<2> = IWRRSelectorByWider::new_const([1000, 1]);
+
+#[test]
+fn test_const_new() {
+    //常量构建的选择器与运行时构建的选择器相同
+    let mut selector = STATIC_SELECTOR.clone();
+    let mut runtime = IWRRSelector::new([5, 3, 1]);
+    assert_eq!(selector.max_weight(), 5);
+    assert_eq!(selector.eligible_len(), 3);
+    for _ in 0..100 {
+        assert_eq!(selector.select(), runtime.select());
+    }
+
+    let mut selector = CONST_SELECTOR;
+    assert_eq!(selector.cycle_len(), 1001);
+    assert_eq!(selector.take_cycle().filter(|pos| *pos == 1).count(), 1);
+    assert_eq!(GenericIWRRSelector::<u16, 2>::new_const([0, 7]).max_weight(), 7);
+}
+
+#[test]
+#[should_panic(expected = "invalid weight")]
+fn test_const_new_invalid_weight() {
+    IWRRSelectorByWider::new_const([1, usize::MAX]);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde() {
+    let mut selector = IWRRSelector::new([5, 3, 0, 8, 1]);
+    selector.set_change_policy(ChangePolicy::NextCycle);
+    for _ in 0..7 {
+        selector.select();
+    }
+    selector.select_run(3);
+    selector.change_weight(2, 4);
+
+    //状态和权重可以完整的往返序列化，并继续相同的选择序列
+    let json = serde_json::to_string(&selector).unwrap();
+    let mut restored: IWRRSelector<5> = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.pos(), selector.pos());
+    assert_eq!(restored.round(), selector.round());
+    assert_eq!(restored.max_weight(), selector.max_weight());
+    assert_eq!(restored.eligible_len(), selector.eligible_len());
+    assert_eq!(restored.change_policy(), ChangePolicy::NextCycle);
+    assert_eq!(restored.try_weight(2), Some(4));
+    assert_eq!(restored.try_active_weight(2), Some(0));
+    for _ in 0..100 {
+        assert_eq!(restored.select(), selector.select());
+    }
+
+    let mut selector = IWRRSelectorByWider::new([300, 0, 1000]);
+    for _ in 0..500 {
+        selector.select();
+    }
+    let json = serde_json::to_string(&selector).unwrap();
+    let mut restored: IWRRSelectorByWider<3> = serde_json::from_str(&json).unwrap();
+    for _ in 0..2000 {
+        assert_eq!(restored.select(), selector.select());
+    }
+
+    //约简的权重可以往返序列化
+    selector.set_normalized(true);
+    let json = serde_json::to_string(&selector).unwrap();
+    let restored: IWRRSelectorByWider<3> = serde_json::from_str(&json).unwrap();
+    assert!(restored.is_normalized());
+    assert_eq!(restored.effective_weights(), [3, 0, 10]);
+    assert_eq!(restored.try_weight(2), Some(1000));
+
+    //减少被提前连续选择的位置的权重后，状态仍然可以往返序列化
+    for policy in [ChangePolicy::Immediate, ChangePolicy::NextRound] {
+        let mut selector = IWRRSelector::new([5, 1]);
+        selector.set_change_policy(policy);
+        assert_eq!(selector.select_run(5), (0, 5));
+        assert_eq!(selector.change_weight(0, 2), Some(5));
+        selector.select();
+        let json = serde_json::to_string(&selector).unwrap();
+        let mut restored: IWRRSelector<2> = serde_json::from_str(&json).unwrap();
+        for _ in 0..100 {
+            assert_eq!(restored.select(), selector.select());
+        }
+    }
+
+    //不可用的位置可以往返序列化，没有可用状态的旧状态反序列化后所有位置都可用
+    let mut selector = IWRRSelector::new([3, 2, 1]);
+    selector.set_available(1, false);
+    selector.select();
+    let mut value = serde_json::to_value(&selector).unwrap();
+    let mut restored: IWRRSelector<3> = serde_json::from_value(value.clone()).unwrap();
+    assert!(!restored.is_available(1));
+    assert_eq!(restored.unavailable_len(), 1);
+    for _ in 0..100 {
+        assert_eq!(restored.select(), selector.select());
+    }
+    value.as_object_mut().unwrap().remove("available");
+    let restored: IWRRSelector<3> = serde_json::from_value(value).unwrap();
+    assert!(restored.is_available(1));
+    assert_eq!(restored.unavailable_len(), 0);
+
+    //反序列化时校验状态
+    let selector = IWRRSelector::new([5, 3, 1]);
+    let value = serde_json::to_value(&selector).unwrap();
+    let invalids = [
+        ("pos", serde_json::json!(4)),
+        ("round", serde_json::json!(255)),
+        ("max_weight", serde_json::json!(3)),
+        ("weights", serde_json::json!([5, 255, 1])),
+        ("weights", serde_json::json!([5, 3])),
+        ("pending", serde_json::json!([5, 3, 2])),
+        ("skips", serde_json::json!([5, 0, 0])),
+    ];
+    for (key, invalid) in invalids {
+        let mut value = value.clone();
+        value[key] = invalid;
+        assert!(serde_json::from_value::<IWRRSelector<3>>(value).is_err(), "{}", key);
+    }
+
+    let mut value = value.clone();
+    value["pos"] = serde_json::json!(3);
+    value["round"] = serde_json::json!(4);
+    let mut restored: IWRRSelector<3> = serde_json::from_value(value.clone()).unwrap();
+    assert_eq!(restored.select(), 0);
+    assert_eq!(restored.round(), 0);
+
+    //轮数为权重上限减一时，选择不会溢出
+    value["round"] = serde_json::json!(254);
+    let mut restored: IWRRSelector<3> = serde_json::from_value(value).unwrap();
+    assert_eq!(restored.select(), 0);
+    assert_eq!(restored.round(), 0);
+}
+
+#[test]
+#[allow(clippy::manual_saturating_arithmetic)]
 fn test_msb() {
     println!("{}, {}", get_msb(0), 0 >> get_msb(0).checked_sub(2).unwrap_or(0));
     println!("{}, {}", get_msb(1), 1 >> get_msb(1).checked_sub(2).unwrap_or(0));